@@ -90,8 +90,8 @@ struct StatusContext {
 fn status_context(code: u16) -> StatusContext {
     if let Some(info) = STATUS_CODES.get(&code) {
         StatusContext {
-            name: info.name.clone(),
-            summary: info.summary.clone(),
+            name: info.name.to_string(),
+            summary: info.summary.to_string(),
         }
     } else {
         warn!("Missing status code metadata for {code}; using fallback");
@@ -1183,7 +1183,7 @@ async fn main() -> Result<()> {
     }
 
     // Pick the highest fun score. If tied, keep the first (deterministic).
-    accepted.sort_by(|a, b| b.1.score.cmp(&a.1.score));
+    accepted.sort_by_key(|b| std::cmp::Reverse(b.1.score));
     let (gag, best_fun) = accepted
         .into_iter()
         .next()
@@ -22,18 +22,150 @@ pub struct CliOptions {
     /// Liten address, defaults to `127.0.0.1``.
     /// Env: HTTPET_LISTEN_ADDRESS
     pub listen_address: String,
-    #[clap(long, short, default_value = "localhost", env = "HTTPET_BASE_DOMAIN")]
+    #[clap(
+        long,
+        short,
+        default_value = "localhost",
+        env = "HTTPET_BASE_DOMAIN",
+        value_delimiter = ','
+    )]
     /// Base domain, defaults to localhost, needs to be httpet.org in prod.
-    /// Env: HTTPET_BASE_DOMAIN
-    pub base_domain: String,
+    /// Repeat it (or comma-separate the env var) to serve several domains, the first is
+    /// the primary used for links. Env: HTTPET_BASE_DOMAIN
+    pub base_domain: Vec<String>,
 
     #[clap(long, env = "HTTPET_FRONTEND_URL")]
     /// Frontend URL, eg `https://httpet.org`. Env: HTTPET_FRONTEND_URL
     /// If not set, will be constructed from listen address and port.
     pub frontend_url: Option<Url>,
 
+    #[clap(long, env = "HTTPET_PUBLIC_BASE_URL_OVERRIDE")]
+    /// Canonical public URL for absolute links outside HTML pages, such as the sitemap,
+    /// eg a CDN hostname. Falls back to the frontend URL. Env: HTTPET_PUBLIC_BASE_URL_OVERRIDE
+    pub public_base_url_override: Option<Url>,
+
     #[clap(long, short, env = "HTTPET_DATABASE_PATH")]
     /// Path to the database file, eg `/data/httpet.sqlite`.
     /// Env: HTTPET_DATABASE_PATH
     pub database_path: Option<String>,
+
+    #[clap(long, env = "HTTPET_STRONG_ETAGS")]
+    /// Derive image ETags from a hash of the file content instead of size and mtime.
+    /// Costs a file read on every conditional request. Env: HTTPET_STRONG_ETAGS
+    pub strong_etags: bool,
+
+    #[clap(long, env = "HTTPET_BEHIND_PROXY")]
+    /// Run behind a reverse proxy: honour `X-Forwarded-Proto`, log the client address the
+    /// proxy reports in `X-Forwarded-For`, and always set secure cookies.
+    /// Only use this with a trusted front proxy, otherwise clients can spoof their address
+    /// and scheme. Env: HTTPET_BEHIND_PROXY
+    pub behind_proxy: bool,
+
+    #[clap(
+        long,
+        env = "HTTPET_IMAGE_FORMATS",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "jpeg,webp"
+    )]
+    /// Image formats considered when negotiating with clients, eg `jpeg,webp`.
+    /// JPEG is always served as the fallback. Env: HTTPET_IMAGE_FORMATS
+    pub image_formats: Vec<crate::web::ImageFormat>,
+
+    #[clap(long, env = "HTTPET_THEME")]
+    /// Theme name, loads `static/themes/<theme>.css` after the default styles so it can
+    /// override the CSS variables. Falls back to the default theme if the file is missing.
+    /// Env: HTTPET_THEME
+    pub theme: Option<String>,
+
+    #[clap(long, env = "HTTPET_NO_MINIFY_HTML")]
+    /// Serve rendered HTML as-is instead of minifying it. HTML is never minified
+    /// with `--debug`. Env: HTTPET_NO_MINIFY_HTML
+    pub no_minify_html: bool,
+
+    #[clap(long, env = "HTTPET_FRAME_OPTIONS", value_enum, default_value = "deny")]
+    /// Framing policy for the admin, vote and home pages, image and info pages can always
+    /// be embedded. Env: HTTPET_FRAME_OPTIONS
+    pub frame_options: FrameOptions,
+
+    #[clap(long, env = "HTTPET_OPTIMIZE_UPLOADS")]
+    /// Re-encode uploads as progressive JPEGs with optimized tables when that's smaller,
+    /// logging the bytes saved. Env: HTTPET_OPTIMIZE_UPLOADS
+    pub optimize_uploads: bool,
+
+    #[clap(long, default_value_t = 1, env = "HTTPET_LEADERBOARD_MIN_VOTES")]
+    /// Minimum votes in the last week for a pet to appear in the home page top votes.
+    /// Env: HTTPET_LEADERBOARD_MIN_VOTES
+    pub leaderboard_min_votes: u32,
+
+    #[clap(long, env = "HTTPET_PLACEHOLDER_IMAGES")]
+    /// Serve a "coming soon" placeholder for every code of an enabled pet with no images yet.
+    /// Env: HTTPET_PLACEHOLDER_IMAGES
+    pub placeholder_images: bool,
+
+    #[clap(
+        long,
+        env = "HTTPET_UPLOAD_FORMATS",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "jpeg,png"
+    )]
+    /// Image formats admins may upload, eg `jpeg` or `jpeg,png`. Uploads are always stored as JPEG.
+    /// Env: HTTPET_UPLOAD_FORMATS
+    pub upload_formats: Vec<crate::web::UploadFormat>,
+
+    #[clap(long, env = "HTTPET_DISABLE_SUBDOMAINS")]
+    /// Serve pets only by path (`/dog/200`) for setups without wildcard DNS. Pet subdomains
+    /// are treated as the apex and links use paths. Env: HTTPET_DISABLE_SUBDOMAINS
+    pub disable_subdomains: bool,
+
+    #[clap(long, env = "HTTPET_CORS_ORIGINS", value_delimiter = ',')]
+    /// Origins allowed to call the vote endpoints cross-origin, eg `https://example.com`.
+    /// Unset means no CORS headers are sent. Env: HTTPET_CORS_ORIGINS
+    pub cors_origins: Vec<String>,
+
+    #[clap(long, default_value_t = crate::constants::DEFAULT_MAX_UPLOAD_BYTES, env = "HTTPET_MAX_UPLOAD_BYTES")]
+    /// Largest image an admin may upload, in bytes. Uploads are streamed to a temp file
+    /// and rejected once they pass this. Env: HTTPET_MAX_UPLOAD_BYTES
+    pub max_upload_bytes: u64,
+
+    #[clap(long, env = "HTTPET_CHECK_REFERER")]
+    /// Reject vote submissions whose `Origin` (or `Referer`) isn't a base domain, a pet
+    /// subdomain, or a `--cors-origins` entry. Env: HTTPET_CHECK_REFERER
+    pub check_referer: bool,
+
+    #[clap(long, env = "HTTPET_GEOIP_DB")]
+    /// Path to a MaxMind country database (`.mmdb`), eg `/data/GeoLite2-Country.mmdb`.
+    /// When set, votes are tallied by the voter's country for the admin page.
+    /// Env: HTTPET_GEOIP_DB
+    pub geoip_db: Option<String>,
+
+    #[clap(long, default_value_t = crate::constants::DEFAULT_PROBE_TIMEOUT_SECS, env = "HTTPET_PROBE_TIMEOUT")]
+    /// Seconds `/readyz` waits for the database before reporting unavailable.
+    /// Env: HTTPET_PROBE_TIMEOUT
+    pub probe_timeout: u64,
+}
+
+/// Who may embed the admin, vote and home pages in a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum FrameOptions {
+    /// Nobody, sends `X-Frame-Options: DENY` and `frame-ancestors 'none'`.
+    #[default]
+    Deny,
+    /// Pages on the same origin, sends `X-Frame-Options: SAMEORIGIN` and `frame-ancestors 'self'`.
+    #[value(name = "sameorigin")]
+    SameOrigin,
+    /// Don't send framing headers.
+    Off,
+}
+
+impl FrameOptions {
+    /// The `X-Frame-Options` and `Content-Security-Policy` values to send, if any.
+    pub fn header_values(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Self::Deny => Some(("DENY", "frame-ancestors 'none'")),
+            Self::SameOrigin => Some(("SAMEORIGIN", "frame-ancestors 'self'")),
+            Self::Off => None,
+        }
+    }
 }
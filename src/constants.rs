@@ -7,9 +7,18 @@ use std::sync::LazyLock;
 /// The default place we put images
 pub static IMAGE_DIR: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::from("./images"));
 
+/// Where `--theme` stylesheets live, served under `/static/themes/`.
+pub const THEMES_DIR: &str = "./static/themes";
+
+/// Operator-supplied status code metadata, merged over the bundled MDN data at startup.
+pub const STATUS_CODE_OVERRIDES_PATH: &str = "./data/status_codes.overrides.json";
+
 /// Custom header for the animal used
 pub const X_HTTPET_ANIMAL: &str = "x-httpet-animal";
 
+/// Most bytes of status images kept in memory, the oldest are dropped past this.
+pub const IMAGE_CACHE_MAX_BYTES: usize = 64 * 1024 * 1024;
+
 /// Max age (in seconds) for image cache entries.
 pub const IMAGE_CACHE_MAX_AGE_SECONDS: u64 = 60 * 60;
 
@@ -29,9 +38,43 @@ pub static IMAGE_CACHE_CONTROL: LazyLock<String> = LazyLock::new(|| {
     )
 });
 
+/// How often (in seconds) the pool of pets with a 404 image is rescanned from disk.
+pub const NOT_FOUND_POOL_REFRESH_SECONDS: u64 = 5 * 60;
+
+/// How often (in seconds) abandoned pending uploads are swept from the temp dir.
+pub const PENDING_UPLOAD_SWEEP_SECONDS: u64 = 5 * 60;
+
+/// Pending uploads older than this (in seconds) are deleted; their session expired long ago
+/// or went away with a restart.
+pub const PENDING_UPLOAD_MAX_AGE_SECONDS: u64 = 60 * 60;
+
 #[cfg(test)]
 /// Base domain used in tests
 pub const TEST_BASE_DOMAIN: &str = "example.org";
 
+/// Default time a readiness probe waits on the database, in seconds.
+pub const DEFAULT_PROBE_TIMEOUT_SECS: u64 = 2;
+
+/// Default largest admin upload, in bytes.
+pub const DEFAULT_MAX_UPLOAD_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Room for an upload form's text fields and multipart framing on top of the largest upload, in bytes.
+pub const UPLOAD_FORM_OVERHEAD_BYTES: u64 = 64 * 1024;
+
+/// Longest text field (pet name, CSRF token, redirect...) accepted in an upload form, in bytes.
+pub const UPLOAD_TEXT_FIELD_MAX_BYTES: usize = 4 * 1024;
+
+/// Methods allowed on the vote endpoints for cross-origin requests.
+pub const VOTE_CORS_ALLOW_METHODS: &str = "GET, POST, OPTIONS";
+
+/// Request headers allowed on the vote endpoints for cross-origin requests.
+pub const VOTE_CORS_ALLOW_HEADERS: &str = "Content-Type";
+
+/// How long (in seconds) browsers may cache a vote preflight response.
+pub const VOTE_CORS_MAX_AGE_SECONDS: u64 = 10 * 60;
+
+/// How long (in seconds) the home page reuses the all-time top pets before summing votes again.
+pub const ALL_TIME_TOP_PETS_CACHE_SECONDS: u64 = 60;
+
 /// Length of CSRF session tokens
 pub const CSRF_SESSION_LENGTH: i64 = 300;
@@ -1,3 +1,4 @@
 //! Database entities
 pub mod pets;
+pub mod vote_countries;
 pub mod votes;
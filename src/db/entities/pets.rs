@@ -1,6 +1,7 @@
 //! Database entities for pets
 
 use sea_orm::entity::prelude::*;
+use sea_orm::{IntoActiveModel, Set};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
@@ -31,6 +32,12 @@ impl PetStatus {
             Self::Enabled => "enabled",
         }
     }
+
+    /// Whether moving to `next` is a normal step. Sending an enabled pet back to
+    /// submitted hides a pet that has images, so it's discouraged.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        !matches!((self, next), (Self::Enabled, Self::Submitted))
+    }
 }
 
 impl std::fmt::Display for PetStatus {
@@ -61,12 +68,15 @@ pub struct Model {
     pub id: i32,
     /// pet name, should be normalised before insertion
     pub name: String,
-    /// whether the pet is enabled for access - can't vote if enabled
+    /// whether the pet is enabled for access - can't vote if enabled.
+    /// Derived from `status` whenever a pet is saved, `status` is the source of truth.
     pub enabled: bool,
     /// status of pet visibility
     pub status: PetStatus,
     /// creation timestamp
     pub created_at: DateTime,
+    /// whether the public can vote for the pet, curated pets can stay listed with voting closed
+    pub votable: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -83,7 +93,18 @@ impl Related<super::votes::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, _insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if let Some(status) = self.status.try_as_ref() {
+            self.enabled = Set(*status == PetStatus::Enabled);
+        }
+        Ok(self)
+    }
+}
 
 impl Entity {
     /// List of enabled pets (Models
@@ -94,6 +115,14 @@ impl Entity {
             .await
     }
 
+    /// Pets currently taking votes
+    pub async fn voting(db: &DatabaseConnection) -> Result<Vec<Model>, DbErr> {
+        Self::find()
+            .filter(Column::Status.eq(PetStatus::Voting))
+            .all(db)
+            .await
+    }
+
     /// List of enabled pet name
     pub async fn enabled_names(db: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
         Ok(Self::find()
@@ -105,6 +134,64 @@ impl Entity {
             .collect())
     }
 
+    /// Fixes rows whose `enabled` flag disagrees with `status`, eg after editing the
+    /// database by hand. Returns how many rows were changed.
+    pub async fn reconcile_enabled<C: ConnectionTrait>(db: &C) -> Result<u64, DbErr> {
+        let enabled = Self::update_many()
+            .col_expr(Column::Enabled, Expr::value(true))
+            .filter(Column::Status.eq(PetStatus::Enabled))
+            .filter(Column::Enabled.eq(false))
+            .exec(db)
+            .await?;
+        let disabled = Self::update_many()
+            .col_expr(Column::Enabled, Expr::value(false))
+            .filter(Column::Status.ne(PetStatus::Enabled))
+            .filter(Column::Enabled.eq(true))
+            .exec(db)
+            .await?;
+        Ok(enabled.rows_affected + disabled.rows_affected)
+    }
+
+    /// Creates the pet with `status`, or moves an existing pet to it.
+    pub async fn upsert_status<C: ConnectionTrait>(
+        db: &C,
+        pet_name: &str,
+        status: PetStatus,
+    ) -> Result<Model, DbErr> {
+        // `enabled` follows `status` when the pet is saved
+        match Self::find_by_name(db, pet_name).await? {
+            Some(model) => {
+                let mut am = model.into_active_model();
+                am.status = Set(status);
+                am.update(db).await
+            }
+            None => {
+                ActiveModel {
+                    name: Set(pet_name.to_string()),
+                    status: Set(status),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await
+            }
+        }
+    }
+
+    /// Opens or closes public voting for an existing pet, returns false if there's no such pet.
+    pub async fn set_votable<C: ConnectionTrait>(
+        db: &C,
+        pet_name: &str,
+        votable: bool,
+    ) -> Result<bool, DbErr> {
+        let Some(model) = Self::find_by_name(db, pet_name).await? else {
+            return Ok(false);
+        };
+        let mut am = model.into_active_model();
+        am.votable = Set(votable);
+        am.update(db).await?;
+        Ok(true)
+    }
+
     /// Find a pet by name, helper function
     pub async fn find_by_name<C: ConnectionTrait>(
         db: &C,
@@ -113,3 +200,19 @@ impl Entity {
         Self::find().filter(Column::Name.eq(pet_name)).one(db).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PetStatus;
+
+    #[test]
+    fn status_transitions_discourage_hiding_enabled_pets() {
+        for status in [PetStatus::Submitted, PetStatus::Voting, PetStatus::Enabled] {
+            assert!(status.can_transition_to(status));
+            assert!(PetStatus::Submitted.can_transition_to(status));
+            assert!(PetStatus::Voting.can_transition_to(status));
+        }
+        assert!(PetStatus::Enabled.can_transition_to(PetStatus::Voting));
+        assert!(!PetStatus::Enabled.can_transition_to(PetStatus::Submitted));
+    }
+}
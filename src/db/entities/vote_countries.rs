@@ -0,0 +1,78 @@
+//! DB storage for where votes came from, aggregated by country
+use std::collections::HashMap;
+
+use sea_orm::{ActiveValue::Set, IntoActiveModel, entity::prelude::*};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "vote_countries")]
+/// Votes from a country on given dates, across all pets
+pub struct Model {
+    #[sea_orm(primary_key)]
+    /// db id
+    pub id: i32,
+    /// ISO 3166-1 alpha-2 country code
+    pub country: String,
+    /// date of vote
+    pub vote_date: Date,
+    /// number of votes from that country on that date
+    pub vote_count: i32,
+}
+
+/// relations for vote countries
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// Adds one vote from `country` to the tally for `vote_date`.
+pub(crate) async fn record_vote_country<C: ConnectionTrait>(
+    db: &C,
+    country: &str,
+    vote_date: Date,
+) -> Result<(), DbErr> {
+    match Entity::find()
+        .filter(
+            Column::Country
+                .eq(country)
+                .and(Column::VoteDate.eq(vote_date)),
+        )
+        .one(db)
+        .await?
+    {
+        Some(model) => {
+            let vote_count = model.vote_count + 1;
+            let mut am = model.into_active_model();
+            am.vote_count = Set(vote_count);
+            am.update(db).await?;
+        }
+        None => {
+            ActiveModel {
+                country: Set(country.to_string()),
+                vote_date: Set(vote_date),
+                vote_count: Set(1),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Total votes per country since `start_date`, busiest country first.
+pub(crate) async fn country_totals<C: ConnectionTrait>(
+    db: &C,
+    start_date: Date,
+) -> Result<Vec<(String, i64)>, DbErr> {
+    let mut totals: HashMap<String, i64> = HashMap::new();
+    for row in Entity::find()
+        .filter(Column::VoteDate.gte(start_date))
+        .all(db)
+        .await?
+    {
+        *totals.entry(row.country).or_default() += i64::from(row.vote_count);
+    }
+    let mut totals: Vec<(String, i64)> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(totals)
+}
@@ -1,8 +1,12 @@
 //! DB storage for votes on pets
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use chrono::Utc;
-use sea_orm::{ActiveValue::Set, IntoActiveModel, TransactionTrait, entity::prelude::*};
+use sea_orm::sea_query::{Alias, Query};
+use sea_orm::{
+    ActiveValue::Set, IntoActiveModel, StatementBuilder, TransactionTrait, entity::prelude::*,
+};
 
 use crate::{error::HttpetError, web::normalize_pet_name_strict};
 
@@ -41,9 +45,11 @@ impl Related<super::pets::Entity> for Entity {
 
 impl ActiveModelBehavior for ActiveModel {}
 
+/// Records a vote for `name`, plus the voter's country when GeoIP resolved one.
 pub(crate) async fn record_vote(
     db: &Arc<DatabaseConnection>,
     name: &str,
+    country: Option<&str>,
 ) -> Result<(), HttpetError> {
     let name = normalize_pet_name_strict(name)?;
     let db_txn = db.begin().await?;
@@ -51,6 +57,7 @@ pub(crate) async fn record_vote(
     let pet = super::pets::Entity::find_by_name(&db_txn, &name).await?;
 
     let pet_id = match pet {
+        Some(model) if !model.votable => return Err(HttpetError::VotingClosed(name)),
         Some(model) => model.id,
         None => {
             let active = super::pets::ActiveModel {
@@ -86,7 +93,31 @@ pub(crate) async fn record_vote(
             active.insert(&db_txn).await?
         }
     };
+    if let Some(country) = country {
+        super::vote_countries::record_vote_country(&db_txn, country, today).await?;
+    }
     db_txn.commit().await?;
 
     Ok(())
 }
+
+/// All-time vote totals keyed by pet id, summed across every date.
+pub(crate) async fn vote_totals<C: ConnectionTrait>(db: &C) -> Result<HashMap<i32, i64>, DbErr> {
+    let total_query = Query::select()
+        .from(Entity)
+        .column(Column::PetId)
+        .expr_as(
+            Expr::col(Column::VoteCount).sum(),
+            Alias::new("total_votes"),
+        )
+        .group_by_col(Column::PetId)
+        .to_owned();
+    let total_stmt = StatementBuilder::build(&total_query, &db.get_database_backend());
+    let mut vote_totals: HashMap<i32, i64> = HashMap::new();
+    for row in db.query_all(total_stmt).await? {
+        let pet_id: i32 = row.try_get("", "pet_id")?;
+        let total_votes: i64 = row.try_get("", "total_votes")?;
+        vote_totals.insert(pet_id, total_votes);
+    }
+    Ok(vote_totals)
+}
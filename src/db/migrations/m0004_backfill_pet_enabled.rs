@@ -0,0 +1,25 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let backend = manager.get_database_backend();
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                backend,
+                "UPDATE pets SET enabled = (status = 'e')".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // the backfill only makes `enabled` agree with `status`, there's nothing to undo
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(VoteCountries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(VoteCountries::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(VoteCountries::Country)
+                            .string_len(2)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(VoteCountries::VoteDate).date().not_null())
+                    .col(
+                        ColumnDef::new(VoteCountries::VoteCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx_vote_countries_country_date")
+                            .table(VoteCountries::Table)
+                            .col(VoteCountries::Country)
+                            .col(VoteCountries::VoteDate)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(VoteCountries::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum VoteCountries {
+    Table,
+    Id,
+    Country,
+    VoteDate,
+    VoteCount,
+}
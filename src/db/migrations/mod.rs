@@ -4,6 +4,9 @@ use sea_orm_migration::prelude::*;
 mod m0001_create_pets_votes;
 mod m0002_add_pet_status;
 mod m0003_pet_status_char;
+mod m0004_backfill_pet_enabled;
+mod m0005_create_vote_countries;
+mod m0006_add_pet_votable;
 
 /// Define the Migrator struct
 pub struct Migrator;
@@ -15,6 +18,9 @@ impl MigratorTrait for Migrator {
             Box::new(m0001_create_pets_votes::Migration),
             Box::new(m0002_add_pet_status::Migration),
             Box::new(m0003_pet_status_char::Migration),
+            Box::new(m0004_backfill_pet_enabled::Migration),
+            Box::new(m0005_create_vote_countries::Migration),
+            Box::new(m0006_add_pet_votable::Migration),
         ]
     }
 }
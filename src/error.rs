@@ -19,10 +19,21 @@ pub enum HttpetError {
     },
     /// Missing or invalid session
     Unauthorized,
+    /// The request came from somewhere it isn't allowed to, with the reason for the logs
+    Forbidden(String),
+    /// Public voting has been closed for the named pet
+    VotingClosed(String),
+    /// An upload went over the configured size limit
+    PayloadTooLarge {
+        /// The configured limit in bytes, quoted back in the response
+        limit: u64,
+    },
     /// When DB operations fail
     DatabaseError(sea_orm::DbErr),
     /// When a requested resource is not found
     NotFound(String),
+    /// An apex image was requested before any pets are enabled
+    NoPetsEnabled,
     /// When an internal server error occurs
     InternalServerError(String),
 
@@ -33,6 +44,16 @@ pub enum HttpetError {
     NeedsVote(String, String),
 }
 
+/// The reason a [HttpetError::NotFound] was raised, attached to the response extensions
+/// so the 404 middleware can surface it in debug mode.
+#[derive(Clone, Debug)]
+pub struct NotFoundReason(pub String);
+
+/// Marks a 404 raised because no pets are enabled yet, so the 404 page can explain
+/// that rather than blaming the URL.
+#[derive(Clone, Copy, Debug)]
+pub struct NoPetsEnabled;
+
 impl From<sea_orm::DbErr> for HttpetError {
     fn from(err: sea_orm::DbErr) -> Self {
         HttpetError::DatabaseError(err)
@@ -98,6 +119,30 @@ impl IntoResponse for HttpetError {
                 *response.status_mut() = axum::http::StatusCode::UNAUTHORIZED;
                 response
             }
+            HttpetError::Forbidden(reason) => {
+                info!("Forbidden request received: {}", reason);
+                let mut response =
+                    axum::response::Response::new(axum::body::Body::from("Forbidden"));
+                *response.status_mut() = axum::http::StatusCode::FORBIDDEN;
+                response
+            }
+            HttpetError::VotingClosed(name) => {
+                info!("Vote for {} rejected, voting is closed", name);
+                let mut response = axum::response::Response::new(axum::body::Body::from(format!(
+                    "Voting is closed for {name}, thanks for stopping by!"
+                )));
+                *response.status_mut() = axum::http::StatusCode::FORBIDDEN;
+                response
+            }
+            HttpetError::PayloadTooLarge { limit } => {
+                info!("Upload over the size limit of {} bytes rejected", limit);
+                let mut response = axum::response::Response::new(axum::body::Body::from(format!(
+                    "Payload Too Large: uploads are limited to {limit} bytes ({:.1} MiB).",
+                    limit as f64 / (1024.0 * 1024.0)
+                )));
+                *response.status_mut() = axum::http::StatusCode::PAYLOAD_TOO_LARGE;
+                response
+            }
             HttpetError::DatabaseError(err) => {
                 tracing::error!("Database error: {}", err);
                 let mut response =
@@ -110,6 +155,15 @@ impl IntoResponse for HttpetError {
                 let mut response =
                     axum::response::Response::new(axum::body::Body::from("Not Found"));
                 *response.status_mut() = axum::http::StatusCode::NOT_FOUND;
+                response.extensions_mut().insert(NotFoundReason(url));
+                response
+            }
+            HttpetError::NoPetsEnabled => {
+                info!("404 no pets are enabled yet");
+                let mut response =
+                    axum::response::Response::new(axum::body::Body::from("Not Found"));
+                *response.status_mut() = axum::http::StatusCode::NOT_FOUND;
+                response.extensions_mut().insert(NoPetsEnabled);
                 response
             }
             HttpetError::InternalServerError(message) => {
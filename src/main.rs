@@ -20,7 +20,9 @@
 use std::process::ExitCode;
 
 use clap::Parser;
-use httpet::{config::setup_logging, status_codes::STATUS_CODES};
+use httpet::{
+    config::setup_logging, constants::STATUS_CODE_OVERRIDES_PATH, status_codes::STATUS_CODES,
+};
 use sea_orm_migration::MigratorTrait;
 use tokio::signal::{unix::SignalKind, unix::signal};
 use tracing::log::{error, info, warn};
@@ -34,8 +36,15 @@ async fn main() -> ExitCode {
         return ExitCode::FAILURE;
     };
 
-    // to make sure it's loaded
-    let _ = STATUS_CODES;
+    // a bad overrides file is reported here instead of aborting on first use
+    if let Err(err) = httpet::status_codes::init() {
+        error!(
+            "Failed to load status codes (overrides file {}): {:?}",
+            STATUS_CODE_OVERRIDES_PATH, err
+        );
+        return ExitCode::FAILURE;
+    }
+    std::sync::LazyLock::force(&STATUS_CODES);
 
     let db = match httpet::db::connect_db(
         cli.database_path.as_deref().unwrap_or("./db/httpet.sqlite"),
@@ -55,6 +64,15 @@ async fn main() -> ExitCode {
         return ExitCode::FAILURE;
     }
 
+    match httpet::db::entities::pets::Entity::reconcile_enabled(db.as_ref()).await {
+        Ok(0) => {}
+        Ok(fixed) => warn!("Reconciled the enabled flag with status for {} pets", fixed),
+        Err(err) => {
+            error!("Failed to reconcile pet enabled flags: {}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+
     let mut hangup_waiter = match signal(SignalKind::hangup()) {
         Ok(signal) => signal,
         Err(err) => {
@@ -4,34 +4,56 @@ use anyhow::Context;
 use axum::http::header::USER_AGENT;
 use html_escape::decode_html_entities;
 use regex::{Regex, RegexBuilder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 
+use crate::constants::STATUS_CODE_OVERRIDES_PATH;
 use crate::error::HttpetError;
 
 /// MDN reference URL for HTTP status codes.
 pub const MDN_STATUS_URL: &str =
     "https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Status";
 
-/// Metadata for an HTTP status code.
+/// Metadata for an HTTP status code. The text is shared, so pages can hand it out
+/// without copying the strings on every request.
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
 pub struct StatusInfo {
     /// Status name from MDN.
-    pub name: String,
+    pub name: Arc<str>,
     /// Short summary text from MDN.
-    pub summary: String,
+    pub summary: Arc<str>,
     /// MDN reference URL for the status code.
-    pub mdn_url: String,
+    pub mdn_url: Arc<str>,
 }
 
-type StatusCodes = BTreeMap<u16, StatusInfo>;
+/// Status code metadata keyed by code.
+pub type StatusCodes = BTreeMap<u16, StatusInfo>;
 
-/// Global status code metadata loaded at startup.
+/// Replacement metadata for a single status code; fields left out keep the MDN value.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct StatusInfoOverride {
+    /// Replacement status name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Replacement summary text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Replacement reference URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mdn_url: Option<String>,
+}
+
+/// Overrides keyed by status code, in the same shape as `data/status_codes.json`.
+pub type StatusOverrides = BTreeMap<u16, StatusInfoOverride>;
+
+/// Global status code metadata loaded at startup. `main` runs [`init`] first and exits
+/// cleanly on an error, so the abort here is only reached if the file changes in between.
 pub static STATUS_CODES: LazyLock<StatusCodes> = LazyLock::new(|| match init() {
     Ok(status_codes) => status_codes,
     Err(err) => {
@@ -40,8 +62,47 @@ pub static STATUS_CODES: LazyLock<StatusCodes> = LazyLock::new(|| match init() {
     }
 });
 
-/// Parse the bundled status code metadata; called during startup.
+/// Lowercases a status name, dropping apostrophes and joining the words with hyphens.
+pub fn status_slug(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for ch in name.chars().filter(|ch| !matches!(ch, '\'' | '\u{2019}')) {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Status name slugs (eg `not-found`) mapped to their codes; more than one code
+/// means the name is shared and the slug is ambiguous.
+pub type StatusSlugs = BTreeMap<String, Vec<u16>>;
+
+/// Builds the slug lookup for a set of status codes.
+pub fn status_slugs(status_codes: &StatusCodes) -> StatusSlugs {
+    let mut slugs = StatusSlugs::new();
+    for (code, info) in status_codes {
+        slugs
+            .entry(status_slug(&info.name))
+            .or_default()
+            .push(*code);
+    }
+    slugs
+}
+
+/// Parse the bundled status code metadata and merge any overrides file over it;
+/// called during startup.
 pub fn init() -> Result<StatusCodes, HttpetError> {
+    let mut res = bundled_status_codes()?;
+    apply_overrides(
+        &mut res,
+        load_overrides(Path::new(STATUS_CODE_OVERRIDES_PATH))?,
+    );
+    Ok(res)
+}
+
+fn bundled_status_codes() -> Result<StatusCodes, HttpetError> {
     let raw = include_str!(concat!(
         env!("CARGO_MANIFEST_DIR"),
         "/data/status_codes.json"
@@ -50,6 +111,44 @@ pub fn init() -> Result<StatusCodes, HttpetError> {
     Ok(res)
 }
 
+/// Reads an overrides file; a missing file means there's nothing to override.
+pub fn load_overrides(path: &Path) -> Result<StatusOverrides, HttpetError> {
+    match fs::read_to_string(path) {
+        Ok(raw) => Ok(serde_json::from_str(&raw)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(StatusOverrides::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes the overrides file, creating its directory if needed.
+pub fn save_overrides(path: &Path, overrides: &StatusOverrides) -> Result<(), HttpetError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let output = serde_json::to_string_pretty(overrides)?;
+    fs::write(path, format!("{output}\n"))?;
+    Ok(())
+}
+
+/// Merges overrides over the base metadata, skipping codes the base map doesn't know.
+pub fn apply_overrides(status_codes: &mut StatusCodes, overrides: StatusOverrides) {
+    for (code, status_override) in overrides {
+        let Some(info) = status_codes.get_mut(&code) else {
+            tracing::warn!(code, "Ignoring override for unknown status code");
+            continue;
+        };
+        if let Some(name) = status_override.name {
+            info.name = name.into();
+        }
+        if let Some(summary) = status_override.summary {
+            info.summary = summary.into();
+        }
+        if let Some(mdn_url) = status_override.mdn_url {
+            info.mdn_url = mdn_url.into();
+        }
+    }
+}
+
 /// Fetches the MDN status code reference page.
 pub fn fetch_status_page() -> anyhow::Result<String> {
     let mut response = ureq::get(MDN_STATUS_URL)
@@ -148,3 +247,66 @@ pub fn write_status_codes(
         .with_context(|| format!("Failed to write {}", path.display()))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_slugs_group_shared_names() {
+        assert_eq!(status_slug("Not Found"), "not-found");
+        assert_eq!(status_slug("I'm a teapot"), "im-a-teapot");
+        assert_eq!(
+            status_slug("HTTP Version Not Supported"),
+            "http-version-not-supported"
+        );
+
+        let info = |name: &str| StatusInfo {
+            name: name.into(),
+            summary: "".into(),
+            mdn_url: "".into(),
+        };
+        let status_codes: StatusCodes = [
+            (404, info("Not Found")),
+            (420, info("Enhance Your Calm")),
+            (429, info("Enhance your calm")),
+        ]
+        .into_iter()
+        .collect();
+        let slugs = status_slugs(&status_codes);
+        assert_eq!(slugs.get("not-found"), Some(&vec![404]));
+        assert_eq!(slugs.get("enhance-your-calm"), Some(&vec![420, 429]));
+    }
+
+    #[test]
+    fn overrides_replace_only_the_given_fields() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("status_codes.overrides.json");
+        fs::write(
+            &path,
+            r#"{"404": {"summary": "Nobody here but us pets."}, "999": {"name": "Nope"}}"#,
+        )
+        .expect("write overrides");
+
+        let mut status_codes = bundled_status_codes().expect("bundled status codes");
+        let base = status_codes.get(&404).cloned().expect("404 metadata");
+        let teapot = status_codes.get(&418).cloned();
+        apply_overrides(
+            &mut status_codes,
+            load_overrides(&path).expect("load overrides"),
+        );
+
+        let info = status_codes.get(&404).expect("404 metadata");
+        assert_eq!(&*info.summary, "Nobody here but us pets.");
+        assert_eq!(info.name, base.name);
+        assert_eq!(info.mdn_url, base.mdn_url);
+        assert_eq!(status_codes.get(&418).cloned(), teapot);
+        assert!(!status_codes.contains_key(&999));
+
+        assert!(
+            load_overrides(&dir.path().join("missing.json"))
+                .expect("missing file is fine")
+                .is_empty()
+        );
+    }
+}
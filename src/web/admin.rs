@@ -3,10 +3,12 @@ use super::flash;
 use super::images::{
     ImageCacheHeaders, apply_cache_headers, is_not_modified, not_modified_response,
 };
+use super::middleware::AnimalDomain;
+use super::pending_upload;
 use super::prelude::*;
+use super::spooled_upload::{self, SpooledUpload};
 use crate::constants::X_HTTPET_ANIMAL;
-use crate::db::entities::{pets, votes};
-use crate::status_codes::{self, STATUS_CODES};
+use crate::db::entities::{pets, vote_countries, votes};
 use axum::extract::{Form, Multipart, Path, State};
 use axum::http::HeaderMap;
 use axum::response::{Redirect, Response};
@@ -15,16 +17,42 @@ use image::ImageDecoder;
 use sea_orm::sea_query::{Alias, Expr, Query};
 use sea_orm::{
     ColumnTrait, DatabaseBackend, EntityTrait, QueryFilter, QueryOrder, StatementBuilder,
+    TransactionTrait,
 };
 use std::collections::{HashMap, HashSet};
-use std::io::{Cursor, ErrorKind};
+use std::io::ErrorKind;
 use std::path::Path as StdPath;
 use std::str::FromStr;
-use tracing::{debug, instrument};
+use tracing::{debug, info, instrument};
+
+/// JPEG quality used when re-encoding uploads.
+const UPLOAD_JPEG_QUALITY: u8 = 85;
+
+/// Input image formats that admin uploads can be decoded from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum UploadFormat {
+    /// JPEG photos.
+    Jpeg,
+    /// PNG images.
+    Png,
+}
+
+impl UploadFormat {
+    fn matches(self, format: image::ImageFormat) -> bool {
+        matches!(
+            (self, format),
+            (Self::Jpeg, image::ImageFormat::Jpeg) | (Self::Png, image::ImageFormat::Png)
+        )
+    }
+}
 
 #[derive(Deserialize)]
 pub(crate) struct PetUpdateForm {
+    csrf_token: String,
     status: String,
+    force: Option<String>,
+    /// Left alone when missing, so status-only posts don't close voting.
+    votable: Option<bool>,
 }
 
 #[derive(Deserialize)]
@@ -33,6 +61,28 @@ pub(crate) struct PetCreateForm {
     status: String,
 }
 
+#[derive(Deserialize)]
+pub(crate) struct CsrfForm {
+    csrf_token: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct CopyImageForm {
+    csrf_token: String,
+    pet: String,
+    status_code: u16,
+    target_pet: String,
+    target_status_code: u16,
+    overwrite: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct StatusInfoForm {
+    csrf_token: String,
+    name: String,
+    summary: String,
+}
+
 #[derive(Deserialize)]
 pub(crate) struct PetDeleteForm {
     csrf_token: String,
@@ -42,6 +92,7 @@ pub(crate) struct PetDeleteForm {
 #[derive(Deserialize)]
 pub(crate) struct PetStatusPath {
     name: String,
+    #[serde(deserialize_with = "deserialize_status_code")]
     status_code: u16,
 }
 
@@ -51,8 +102,10 @@ struct AdminPetView {
     status_label: String,
     status_class: String,
     is_enabled: bool,
+    votable: bool,
     chart_svg: String,
     vote_total: i64,
+    image_count: usize,
 }
 
 #[derive(Template, WebTemplate)]
@@ -65,10 +118,17 @@ pub(crate) struct AdminTemplate {
     has_orphan_pets: bool,
     orphan_pets: Vec<String>,
     state: AppState,
+    /// Base domain the admin is browsing, public links stay on it.
+    base_domain: String,
     csrf_token: String,
     has_flash: bool,
     flash_message: String,
     flash_class: String,
+    theme_stylesheet: Option<String>,
+    /// Only shown when a GeoIP database is configured.
+    has_geoip: bool,
+    country_totals: Vec<(String, i64)>,
+    country_chart_svg: String,
 }
 
 #[derive(Template, WebTemplate)]
@@ -86,6 +146,7 @@ pub(crate) struct AdminPetTemplate {
     has_flash: bool,
     flash_message: String,
     flash_class: String,
+    theme_stylesheet: Option<String>,
 }
 
 #[derive(Template, WebTemplate)]
@@ -93,15 +154,31 @@ pub(crate) struct AdminPetTemplate {
 pub(crate) struct AdminUploadTemplate {
     pet_name: String,
     status_code: u16,
-    status_name: String,
-    status_summary: String,
-    status_mdn_url: String,
+    status_name: Arc<str>,
+    status_summary: Arc<str>,
+    status_mdn_url: Arc<str>,
     has_existing: bool,
     existing_image_url: String,
+    has_pending: bool,
+    csrf_token: String,
+    has_flash: bool,
+    flash_message: String,
+    flash_class: String,
+    theme_stylesheet: Option<String>,
+}
+
+#[derive(Template, WebTemplate)]
+#[template(path = "admin_status_code.html")]
+pub(crate) struct AdminStatusCodeTemplate {
+    status_code: u16,
+    status_name: Arc<str>,
+    status_summary: Arc<str>,
+    status_mdn_url: Arc<str>,
     csrf_token: String,
     has_flash: bool,
     flash_message: String,
     flash_class: String,
+    theme_stylesheet: Option<String>,
 }
 
 #[derive(Template, WebTemplate)]
@@ -114,9 +191,11 @@ pub(crate) struct DeletePetTemplate {
     has_flash: bool,
     flash_message: String,
     flash_class: String,
+    theme_stylesheet: Option<String>,
 }
 
 pub(crate) async fn admin_handler(
+    domain: AnimalDomain,
     State(state): State<AppState>,
     session: Session,
 ) -> Result<AdminTemplate, HttpetError> {
@@ -134,29 +213,19 @@ pub(crate) async fn admin_handler(
         .collect();
 
     let mut pets: Vec<AdminPetView> = Vec::new();
-    let total_query = Query::select()
-        .from(votes::Entity)
-        .column(votes::Column::PetId)
-        .expr_as(
-            Expr::col(votes::Column::VoteCount).sum(),
-            Alias::new("total_votes"),
-        )
-        .group_by_col(votes::Column::PetId)
-        .to_owned();
-    let total_stmt = StatementBuilder::build(&total_query, &DatabaseBackend::Sqlite);
-    let total_rows = state.db.query_all(total_stmt).await?;
-    let mut vote_totals: HashMap<i32, i64> = HashMap::new();
-    for row in total_rows {
-        let pet_id: i32 = row.try_get("", "pet_id")?;
-        let total_votes: i64 = row.try_get("", "total_votes")?;
-        vote_totals.insert(pet_id, total_votes);
-    }
+    let vote_totals = votes::vote_totals(state.db.as_ref()).await?;
     let votes = votes::Entity::find()
         .filter(votes::Column::VoteDate.gte(start_date))
         .order_by_asc(votes::Column::VoteDate)
         .all(state.db.as_ref())
         .await?;
 
+    let image_counts = count_pet_images(
+        &state.image_dir,
+        pet_db.iter().map(|pet| pet.name.clone()).collect(),
+    )
+    .await?;
+
     for pet in pet_db {
         let chart_svg = if pet.status == pets::PetStatus::Enabled {
             String::new()
@@ -169,19 +238,30 @@ pub(crate) async fn admin_handler(
             let vote_counts = build_vote_series(&date_labels, Some(&pet_votes));
             render_vote_chart(&pet.name, &vote_counts)
         };
+        let image_count = image_counts.get(&pet.name).copied().unwrap_or(0);
         pets.push(AdminPetView {
             name: pet.name,
             status_label: pet.status.to_string(),
             status_class: pet.status.to_string(),
             is_enabled: pet.status == pets::PetStatus::Enabled,
+            votable: pet.votable,
             chart_svg,
             vote_total: vote_totals.get(&pet.id).copied().unwrap_or(0),
+            image_count,
         });
     }
 
     let start_label = date_labels.first().map(format_date).unwrap_or_default();
     let end_label = date_labels.last().map(format_date).unwrap_or_default();
 
+    let has_geoip = state.geoip.is_some();
+    let country_totals = if has_geoip {
+        vote_countries::country_totals(state.db.as_ref(), start_date).await?
+    } else {
+        Vec::new()
+    };
+    let country_chart_svg = render_country_chart(&country_totals);
+
     let image_dirs = list_image_dirs(&state.image_dir).await?;
     let mut orphan_pets = Vec::new();
     let mut seen = HashSet::new();
@@ -209,15 +289,21 @@ pub(crate) async fn admin_handler(
         pets,
         start_label,
         end_label,
+        theme_stylesheet: state.theme_stylesheet(),
         state,
+        base_domain: domain.base_domain,
         csrf_token,
         has_flash,
         flash_message,
         flash_class,
+        has_geoip,
+        country_totals,
+        country_chart_svg,
     })
 }
 
 pub(crate) async fn admin_pet_view(
+    domain: AnimalDomain,
     State(state): State<AppState>,
     session: Session,
     Path(name): Path<String>,
@@ -228,7 +314,15 @@ pub(crate) async fn admin_pet_view(
         return Err(HttpetError::NotFound(pet_name));
     };
 
-    let known_set: HashSet<u16> = status_codes::STATUS_CODES.keys().copied().collect();
+    let known_codes: Vec<u16> = state
+        .status_codes
+        .read()
+        .await
+        .codes
+        .keys()
+        .copied()
+        .collect();
+    let known_set: HashSet<u16> = known_codes.iter().copied().collect();
 
     let image_files = list_pet_images(&state.image_dir, &pet_name).await?;
     let mut available_codes = Vec::new();
@@ -249,9 +343,8 @@ pub(crate) async fn admin_pet_view(
     available_codes.sort_unstable();
     available_codes.dedup();
     let available_set: HashSet<u16> = available_codes.iter().copied().collect();
-    let missing_codes: Vec<u16> = status_codes::STATUS_CODES
-        .keys()
-        .copied()
+    let missing_codes: Vec<u16> = known_codes
+        .into_iter()
         .filter(|code| !available_set.contains(code))
         .collect();
 
@@ -277,7 +370,7 @@ pub(crate) async fn admin_pet_view(
 
     Ok(AdminPetTemplate {
         pet_name: pet_name.clone(),
-        public_url: state.pet_base_url(&pet_name),
+        public_url: state.pet_base_url(&domain.base_domain, &pet_name),
         available_codes,
         missing_codes,
         has_unknown_files: !unknown_files.is_empty(),
@@ -288,6 +381,7 @@ pub(crate) async fn admin_pet_view(
         has_flash,
         flash_message,
         flash_class,
+        theme_stylesheet: state.theme_stylesheet(),
     })
 }
 
@@ -308,7 +402,7 @@ pub(crate) async fn admin_pet_upload_view(
         return Err(HttpetError::NotFound(pet_name));
     }
 
-    let Some(info) = STATUS_CODES.get(&path.status_code) else {
+    let Some(info) = state.status_info(path.status_code).await else {
         return Err(HttpetError::NotFound(path.status_code.to_string()));
     };
 
@@ -319,6 +413,11 @@ pub(crate) async fn admin_pet_upload_view(
         Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
     };
 
+    let has_pending = has_existing
+        && pending_upload::get(&session)
+            .await?
+            .is_some_and(|pending| pending.matches(&pet_name, path.status_code));
+
     let csrf_token = csrf_token(&session).await?;
     let flash = flash::take_flash_message(&session).await?;
     let (has_flash, flash_message, flash_class) = match flash {
@@ -329,15 +428,17 @@ pub(crate) async fn admin_pet_upload_view(
     Ok(AdminUploadTemplate {
         pet_name: pet_name.clone(),
         status_code: path.status_code,
-        status_name: info.name.clone(),
-        status_summary: info.summary.clone(),
-        status_mdn_url: info.mdn_url.clone(),
+        status_name: info.name,
+        status_summary: info.summary,
+        status_mdn_url: info.mdn_url,
         has_existing,
         existing_image_url: format!("/admin/pets/{}/images/{}", pet_name, path.status_code),
+        has_pending,
         csrf_token,
         has_flash,
         flash_message,
         flash_class,
+        theme_stylesheet: state.theme_stylesheet(),
     })
 }
 
@@ -362,28 +463,29 @@ pub(crate) async fn admin_pet_image_handler(
         }
         Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
     };
-    let cache_headers = ImageCacheHeaders::from_metadata(&metadata);
+    let bytes = match tokio::fs::read(&image_path).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(HttpetError::NotFound(format!(
+                "{} {}",
+                pet_name, path.status_code
+            )));
+        }
+        Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
+    };
+    let cache_headers = ImageCacheHeaders::for_image(&metadata, &bytes, false);
     if is_not_modified(&headers, &cache_headers) {
         return not_modified_response(&cache_headers);
     }
     let mut builder = Response::builder();
-    match tokio::fs::read(&image_path).await {
-        Ok(bytes) => {
-            if let Ok(value) = HeaderValue::from_str(&pet_name) {
-                builder = builder.header(X_HTTPET_ANIMAL, value);
-            }
-            builder = builder.header(CONTENT_TYPE, "image/jpeg");
-            builder = apply_cache_headers(builder, &cache_headers);
-            builder
-                .body(axum::body::Body::from(bytes))
-                .map_err(HttpetError::from)
-        }
-        Err(err) if err.kind() == ErrorKind::NotFound => Err(HttpetError::NotFound(format!(
-            "{} {}",
-            pet_name, path.status_code
-        ))),
-        Err(err) => Err(HttpetError::InternalServerError(err.to_string())),
+    if let Ok(value) = HeaderValue::from_str(&pet_name) {
+        builder = builder.header(X_HTTPET_ANIMAL, value);
     }
+    builder = builder.header(CONTENT_TYPE, "image/jpeg");
+    builder = apply_cache_headers(builder, &cache_headers);
+    builder
+        .body(axum::body::Body::from(bytes))
+        .map_err(HttpetError::from)
 }
 
 pub(crate) async fn delete_pet_view(
@@ -417,20 +519,39 @@ pub(crate) async fn delete_pet_view(
         has_flash,
         flash_message,
         flash_class,
+        theme_stylesheet: state.theme_stylesheet(),
     })
 }
 
 pub(crate) async fn update_pet_handler(
     State(state): State<AppState>,
+    session: Session,
     Path(name): Path<String>,
     Form(form): Form<PetUpdateForm>,
 ) -> Result<Redirect, HttpetError> {
+    validate_csrf(&session, &form.csrf_token).await?;
     let name = normalize_pet_name_strict(&name)?;
 
     let status_value = form.status.trim().to_ascii_lowercase();
     let status =
         pets::PetStatus::from_str(status_value.as_str()).map_err(|_| HttpetError::BadRequest)?;
+    if let Some(pet) = pets::Entity::find_by_name(state.db.as_ref(), &name).await?
+        && !pet.status.can_transition_to(status)
+    {
+        if form.force.is_none() {
+            // voting isn't tied to the status, so that part of the form still applies
+            if let Some(votable) = form.votable {
+                pets::Entity::set_votable(state.db.as_ref(), &name, votable).await?;
+            }
+            flash::set_flash(&session, flash::FLASH_STATUS_TRANSITION_BLOCKED).await?;
+            return Ok(Redirect::to("/admin/"));
+        }
+        tracing::warn!(pet = %name, from = %pet.status, to = %status, "Forced pet status change");
+    }
     state.create_or_update_pet(&name, status).await?;
+    if let Some(votable) = form.votable {
+        pets::Entity::set_votable(state.db.as_ref(), &name, votable).await?;
+    }
     Ok(Redirect::to("/admin/"))
 }
 
@@ -456,7 +577,7 @@ pub(crate) async fn upload_image_handler(
 ) -> Result<Redirect, HttpetError> {
     let mut pet_name: Option<String> = None;
     let mut status_code: Option<u16> = None;
-    let mut image_bytes: Option<Vec<u8>> = None;
+    let mut image_upload: Option<SpooledUpload> = None;
     let mut csrf_token_value: Option<String> = None;
     let mut redirect_to: Option<String> = None;
     let mut overwrite: bool = false;
@@ -464,29 +585,21 @@ pub(crate) async fn upload_image_handler(
     while let Some(field) = multipart
         .next_field()
         .await
-        .map_err(|err| HttpetError::InternalServerError(err.to_string()))?
+        .map_err(|err| spooled_upload::multipart_error(&err, state.config.max_upload_bytes))?
     {
         let field_name = field.name().unwrap_or_default();
         match field_name {
             "pet" => {
-                let name = field
-                    .text()
-                    .await
-                    .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+                let name = spooled_upload::text_field(field, state.config.max_upload_bytes).await?;
                 pet_name = Some(normalize_pet_name_strict(&name)?);
             }
             "csrf_token" => {
-                let value = field
-                    .text()
-                    .await
-                    .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+                let value =
+                    spooled_upload::text_field(field, state.config.max_upload_bytes).await?;
                 csrf_token_value = Some(value);
             }
             "status_code" => {
-                let code = field
-                    .text()
-                    .await
-                    .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+                let code = spooled_upload::text_field(field, state.config.max_upload_bytes).await?;
                 let parsed = code.parse::<u16>().map_err(|_| HttpetError::BadRequest)?;
                 if !(100..=599).contains(&parsed) {
                     return Err(HttpetError::BadRequest);
@@ -494,17 +607,12 @@ pub(crate) async fn upload_image_handler(
                 status_code = Some(parsed);
             }
             "image" => {
-                let bytes = field
-                    .bytes()
-                    .await
-                    .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
-                image_bytes = Some(bytes.to_vec());
+                image_upload =
+                    Some(spooled_upload::spool_field(field, state.config.max_upload_bytes).await?);
             }
             "redirect_to" => {
-                let value = field
-                    .text()
-                    .await
-                    .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+                let value =
+                    spooled_upload::text_field(field, state.config.max_upload_bytes).await?;
                 redirect_to = Some(value);
             }
             "overwrite" => {
@@ -518,10 +626,25 @@ pub(crate) async fn upload_image_handler(
         .filter(|name| !name.is_empty())
         .ok_or(HttpetError::BadRequest)?;
     let status_code = status_code.ok_or(HttpetError::BadRequest)?;
-    let image_bytes = image_bytes.ok_or(HttpetError::BadRequest)?;
+    let image_upload = image_upload.ok_or(HttpetError::BadRequest)?;
     let csrf_token_value = csrf_token_value.ok_or(HttpetError::BadRequest)?;
     validate_csrf(&session, &csrf_token_value).await?;
-    let image_bytes = normalize_image_to_jpeg(&image_bytes)?;
+    // formats the decoder can't identify are left to normalize_image_to_jpeg to reject
+    if let Some(format) = guess_file_format(image_upload.path())
+        && !state
+            .config
+            .upload_formats
+            .iter()
+            .any(|allowed| allowed.matches(format))
+    {
+        debug!("Rejected upload in disallowed format {:?}", format);
+        flash::set_flash(&session, flash::FLASH_UPLOAD_FORMAT_REJECTED).await?;
+        return Ok(Redirect::to(&format!(
+            "/admin/pets/{}/status/{}",
+            pet_name, status_code
+        )));
+    }
+    let image_bytes = normalize_upload_to_jpeg(image_upload, state.config.optimize_uploads).await?;
 
     let pet_exists = pets::Entity::find_by_name(state.db.as_ref(), &pet_name)
         .await?
@@ -541,6 +664,7 @@ pub(crate) async fn upload_image_handler(
         Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
     };
     if exists && !overwrite {
+        pending_upload::store(&session, &pet_name, status_code, &image_bytes).await?;
         flash::set_flash(&session, flash::FLASH_OVERWRITE_REQUIRED).await?;
         return Ok(Redirect::to(&format!(
             "/admin/pets/{}/status/{}",
@@ -550,6 +674,9 @@ pub(crate) async fn upload_image_handler(
     tokio::fs::write(&image_path, image_bytes)
         .await
         .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+    state.remove_image_variants(&pet_name, status_code).await?;
+    state.invalidate_pet_images(&pet_name).await;
+    pending_upload::discard(&session).await?;
 
     flash::set_flash(&session, flash::FLASH_UPLOAD_SUCCESS).await?;
     let redirect_target = redirect_to
@@ -559,6 +686,133 @@ pub(crate) async fn upload_image_handler(
     Ok(Redirect::to(redirect_target))
 }
 
+/// Creates (or re-statuses) a pet and stores its first image in one step. The pet
+/// change is only committed once the image is on disk, and the image is rolled back
+/// if the commit fails. An existing image for the code is never replaced here, it's
+/// held as a pending upload for the admin to confirm.
+pub(crate) async fn onboard_pet_handler(
+    State(state): State<AppState>,
+    session: Session,
+    mut multipart: Multipart,
+) -> Result<Redirect, HttpetError> {
+    let mut pet_name: Option<String> = None;
+    let mut status: Option<pets::PetStatus> = None;
+    let mut status_code: Option<u16> = None;
+    let mut image_upload: Option<SpooledUpload> = None;
+    let mut csrf_token_value: Option<String> = None;
+    let mut force = false;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| spooled_upload::multipart_error(&err, state.config.max_upload_bytes))?
+    {
+        let field_name = field.name().unwrap_or_default();
+        if field_name == "image" {
+            image_upload =
+                Some(spooled_upload::spool_field(field, state.config.max_upload_bytes).await?);
+            continue;
+        }
+        let field_name = field_name.to_string();
+        let value = spooled_upload::text_field(field, state.config.max_upload_bytes).await?;
+        match field_name.as_str() {
+            "force" => force = true,
+            "name" => pet_name = Some(normalize_pet_name_strict(&value)?),
+            "status" => {
+                status = Some(
+                    pets::PetStatus::from_str(value.trim().to_ascii_lowercase().as_str())
+                        .map_err(|_| HttpetError::BadRequest)?,
+                );
+            }
+            "status_code" => {
+                let parsed = value
+                    .trim()
+                    .parse::<u16>()
+                    .map_err(|_| HttpetError::BadRequest)?;
+                if !(100..=599).contains(&parsed) {
+                    return Err(HttpetError::BadRequest);
+                }
+                status_code = Some(parsed);
+            }
+            "csrf_token" => csrf_token_value = Some(value),
+            _ => {}
+        }
+    }
+
+    let pet_name = pet_name
+        .filter(|name| !name.is_empty())
+        .ok_or(HttpetError::BadRequest)?;
+    let status = status.unwrap_or(pets::PetStatus::Enabled);
+    let status_code = status_code.ok_or(HttpetError::BadRequest)?;
+    let image_upload = image_upload.ok_or(HttpetError::BadRequest)?;
+    let csrf_token_value = csrf_token_value.ok_or(HttpetError::BadRequest)?;
+    validate_csrf(&session, &csrf_token_value).await?;
+    if let Some(format) = guess_file_format(image_upload.path())
+        && !state
+            .config
+            .upload_formats
+            .iter()
+            .any(|allowed| allowed.matches(format))
+    {
+        debug!(
+            "Rejected onboarding upload in disallowed format {:?}",
+            format
+        );
+        flash::set_flash(&session, flash::FLASH_UPLOAD_FORMAT_REJECTED).await?;
+        return Ok(Redirect::to("/admin/"));
+    }
+    // same policy as the status form, hiding an enabled pet needs force
+    if let Some(pet) = pets::Entity::find_by_name(state.db.as_ref(), &pet_name).await?
+        && !pet.status.can_transition_to(status)
+    {
+        if !force {
+            flash::set_flash(&session, flash::FLASH_STATUS_TRANSITION_BLOCKED).await?;
+            return Ok(Redirect::to("/admin/"));
+        }
+        tracing::warn!(pet = %pet_name, from = %pet.status, to = %status, "Forced pet status change");
+    }
+    let image_bytes = normalize_upload_to_jpeg(image_upload, state.config.optimize_uploads).await?;
+
+    // file IO happens before the transaction so the SQLite write lock is held briefly
+    let pet_dir = state.image_dir.join(&pet_name);
+    tokio::fs::create_dir_all(&pet_dir).await?;
+    let image_path = pet_dir.join(format!("{status_code}.jpg"));
+    let exists = match tokio::fs::metadata(&image_path).await {
+        Ok(metadata) => metadata.is_file(),
+        Err(err) if err.kind() == ErrorKind::NotFound => false,
+        Err(err) => return Err(err.into()),
+    };
+    if exists {
+        // the pet is left as it was, replacing an image goes through the usual confirm step
+        pending_upload::store(&session, &pet_name, status_code, &image_bytes).await?;
+        flash::set_flash(&session, flash::FLASH_OVERWRITE_REQUIRED).await?;
+        return Ok(Redirect::to(&format!(
+            "/admin/pets/{}/status/{}",
+            pet_name, status_code
+        )));
+    }
+    tokio::fs::write(&image_path, image_bytes).await?;
+
+    let upserted = async {
+        let db_txn = state.db.as_ref().begin().await?;
+        pets::Entity::upsert_status(&db_txn, &pet_name, status).await?;
+        db_txn.commit().await
+    };
+    if let Err(err) = upserted.await {
+        if let Err(restore_err) = tokio::fs::remove_file(&image_path).await {
+            error!(error=?restore_err, path=%image_path.display(), "Failed to roll back onboarding image");
+        }
+        return Err(err.into());
+    }
+
+    state.refresh_enabled_pets().await?;
+    state.remove_image_variants(&pet_name, status_code).await?;
+    state.invalidate_pet_images(&pet_name).await;
+    info!(pet=%pet_name, status=%status, status_code, "Onboarded pet");
+    flash::set_flash(&session, flash::FLASH_UPLOAD_SUCCESS).await?;
+    Ok(Redirect::to(&format!("/admin/pets/{}", pet_name)))
+}
+
 /// Deletes a pet and its images
 #[instrument(skip_all, fields(name = %name, delete_images=?form.delete_images))]
 pub(crate) async fn delete_pet_post(
@@ -583,21 +837,264 @@ pub(crate) async fn delete_pet_post(
         {
             return Err(HttpetError::InternalServerError(err.to_string()));
         }
+        state.invalidate_pet_images(&pet_name).await;
     }
 
     state.delete_pet(&pet_name).await?;
     Ok(Redirect::to("/admin/"))
 }
 
-/// Ensures image bytes decode cleanly, applies orientation, and re-encodes to JPEG.
-/// Re-encoding strips uploaded metadata (EXIF/XMP/etc) from the output file.
-fn normalize_image_to_jpeg(bytes: &[u8]) -> Result<Vec<u8>, HttpetError> {
+/// Serves the session's pending upload so it can be compared with the current image.
+pub(crate) async fn pending_upload_image(session: Session) -> Result<Response, HttpetError> {
+    let Some(pending) = pending_upload::get(&session).await? else {
+        return Err(HttpetError::NotFound("pending upload".to_string()));
+    };
+    let bytes = match tokio::fs::read(pending.path()).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(HttpetError::NotFound("pending upload".to_string()));
+        }
+        Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
+    };
+    Response::builder()
+        .header(CONTENT_TYPE, "image/jpeg")
+        .header(axum::http::header::CACHE_CONTROL, "no-store")
+        .body(axum::body::Body::from(bytes))
+        .map_err(HttpetError::from)
+}
+
+/// Replaces the existing image with the session's pending upload.
+pub(crate) async fn confirm_pending_upload(
+    State(state): State<AppState>,
+    session: Session,
+    Form(form): Form<CsrfForm>,
+) -> Result<Redirect, HttpetError> {
+    validate_csrf(&session, &form.csrf_token).await?;
+    let Some(pending) = pending_upload::take(&session).await? else {
+        return Ok(Redirect::to("/admin/"));
+    };
+    let bytes = match tokio::fs::read(pending.path()).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Ok(Redirect::to(&format!(
+                "/admin/pets/{}/status/{}",
+                pending.pet, pending.status_code
+            )));
+        }
+        Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
+    };
+    tokio::fs::write(state.image_path(&pending.pet, pending.status_code), bytes)
+        .await
+        .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+    pending_upload::remove_file(&pending).await?;
+    state
+        .remove_image_variants(&pending.pet, pending.status_code)
+        .await?;
+    state.invalidate_pet_images(&pending.pet).await;
+
+    flash::set_flash(&session, flash::FLASH_UPLOAD_SUCCESS).await?;
+    Ok(Redirect::to(&format!("/admin/pets/{}", pending.pet)))
+}
+
+/// Throws away the session's pending upload, keeping the current image.
+pub(crate) async fn cancel_pending_upload(
+    session: Session,
+    Form(form): Form<CsrfForm>,
+) -> Result<Redirect, HttpetError> {
+    validate_csrf(&session, &form.csrf_token).await?;
+    let Some(pending) = pending_upload::take(&session).await? else {
+        return Ok(Redirect::to("/admin/"));
+    };
+    pending_upload::remove_file(&pending).await?;
+    Ok(Redirect::to(&format!(
+        "/admin/pets/{}/status/{}",
+        pending.pet, pending.status_code
+    )))
+}
+
+/// Copies an existing image to another pet and/or status code.
+#[instrument(skip_all, fields(pet = %form.pet, status_code = form.status_code, target_pet = %form.target_pet, target_status_code = form.target_status_code))]
+pub(crate) async fn copy_image_handler(
+    State(state): State<AppState>,
+    session: Session,
+    Form(form): Form<CopyImageForm>,
+) -> Result<Redirect, HttpetError> {
+    validate_csrf(&session, &form.csrf_token).await?;
+
+    let pet_name = normalize_pet_name_strict(&form.pet)?;
+    let target_pet = normalize_pet_name_strict(&form.target_pet)?;
+    if !(100..=599).contains(&form.status_code) || !(100..=599).contains(&form.target_status_code) {
+        return Err(HttpetError::BadRequest);
+    }
+    if pet_name == target_pet && form.status_code == form.target_status_code {
+        return Err(HttpetError::BadRequest);
+    }
+
+    let target_exists = pets::Entity::find_by_name(state.db.as_ref(), &target_pet)
+        .await?
+        .is_some();
+    if !target_exists {
+        return Err(HttpetError::BadRequest);
+    }
+
+    let source_path = state.image_path(&pet_name, form.status_code);
+    match tokio::fs::metadata(&source_path).await {
+        Ok(metadata) if metadata.is_file() => {}
+        Ok(_) => {
+            return Err(HttpetError::NotFound(format!(
+                "{} {}",
+                pet_name, form.status_code
+            )));
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(HttpetError::NotFound(format!(
+                "{} {}",
+                pet_name, form.status_code
+            )));
+        }
+        Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
+    }
+
+    let target_path = state.image_path(&target_pet, form.target_status_code);
+    let target_exists = match tokio::fs::metadata(&target_path).await {
+        Ok(metadata) => metadata.is_file(),
+        Err(err) if err.kind() == ErrorKind::NotFound => false,
+        Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
+    };
+    if target_exists && form.overwrite.is_none() {
+        flash::set_flash(&session, flash::FLASH_OVERWRITE_REQUIRED).await?;
+        return Ok(Redirect::to(&format!(
+            "/admin/pets/{}/status/{}",
+            pet_name, form.status_code
+        )));
+    }
+
+    tokio::fs::create_dir_all(state.image_dir.join(&target_pet))
+        .await
+        .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+    tokio::fs::copy(&source_path, &target_path)
+        .await
+        .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+    state
+        .remove_image_variants(&target_pet, form.target_status_code)
+        .await?;
+    state.invalidate_pet_images(&target_pet).await;
+
+    flash::set_flash(&session, flash::FLASH_IMAGE_COPIED).await?;
+    Ok(Redirect::to(&format!("/admin/pets/{}", target_pet)))
+}
+
+/// Form for editing the displayed name and summary of a status code.
+pub(crate) async fn admin_status_code_view(
+    State(state): State<AppState>,
+    session: Session,
+    Path(StatusCodeParam(status_code)): Path<StatusCodeParam>,
+) -> Result<AdminStatusCodeTemplate, HttpetError> {
+    let Some(info) = state.status_info(status_code).await else {
+        return Err(HttpetError::NotFound(status_code.to_string()));
+    };
+
+    let csrf_token = csrf_token(&session).await?;
+    let flash = flash::take_flash_message(&session).await?;
+    let (has_flash, flash_message, flash_class) = match flash {
+        Some(message) => (true, message.text.to_string(), message.class.to_string()),
+        None => (false, String::new(), String::new()),
+    };
+
+    Ok(AdminStatusCodeTemplate {
+        status_code,
+        status_name: info.name,
+        status_summary: info.summary,
+        status_mdn_url: info.mdn_url,
+        csrf_token,
+        has_flash,
+        flash_message,
+        flash_class,
+        theme_stylesheet: state.theme_stylesheet(),
+    })
+}
+
+/// Saves an edited status code name and summary to the overrides file.
+#[instrument(skip_all, fields(status_code = status_code))]
+pub(crate) async fn update_status_code_handler(
+    State(state): State<AppState>,
+    session: Session,
+    Path(StatusCodeParam(status_code)): Path<StatusCodeParam>,
+    Form(form): Form<StatusInfoForm>,
+) -> Result<Redirect, HttpetError> {
+    validate_csrf(&session, &form.csrf_token).await?;
+
+    let name = form.name.trim();
+    let summary = form.summary.trim();
+    if name.is_empty() || summary.is_empty() {
+        return Err(HttpetError::BadRequest);
+    }
+    state
+        .update_status_info(status_code, name.to_string(), summary.to_string())
+        .await?;
+
+    flash::set_flash(&session, flash::FLASH_STATUS_CODE_UPDATED).await?;
+    Ok(Redirect::to(&format!("/admin/status-codes/{status_code}")))
+}
+
+/// Drops cached images and re-reads enabled pets after images are synced to disk.
+pub(crate) async fn reload_images_handler(
+    State(state): State<AppState>,
+    session: Session,
+    Form(form): Form<CsrfForm>,
+) -> Result<Redirect, HttpetError> {
+    validate_csrf(&session, &form.csrf_token).await?;
+    state.reload_images().await?;
+    flash::set_flash(&session, flash::FLASH_IMAGES_RELOADED).await?;
+    Ok(Redirect::to("/admin/"))
+}
+
+/// Sniffs the image format from the start of a spooled upload.
+fn guess_file_format(path: &StdPath) -> Option<image::ImageFormat> {
+    image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .ok()
+        .and_then(|reader| reader.format())
+}
+
+/// Normalizes a spooled upload on the blocking pool, decoding straight from the temp file.
+async fn normalize_upload_to_jpeg(
+    upload: SpooledUpload,
+    optimize: bool,
+) -> Result<Vec<u8>, HttpetError> {
+    tokio::task::spawn_blocking(move || {
+        if upload.len() < 4 {
+            debug!("Image is too short");
+            return Err(HttpetError::BadRequest);
+        }
+        let reader = image::ImageReader::open(upload.path())?;
+        normalize_reader_to_jpeg(reader, optimize)
+    })
+    .await
+    .map_err(|err| HttpetError::InternalServerError(err.to_string()))?
+}
+
+/// [normalize_reader_to_jpeg] for in-memory image bytes.
+#[cfg(test)]
+fn normalize_image_to_jpeg(bytes: &[u8], optimize: bool) -> Result<Vec<u8>, HttpetError> {
     if bytes.len() < 4 {
         debug!("Image is too short");
         return Err(HttpetError::BadRequest);
     }
+    normalize_reader_to_jpeg(
+        image::ImageReader::new(std::io::Cursor::new(bytes)),
+        optimize,
+    )
+}
 
-    let mut decoder = image::ImageReader::new(Cursor::new(bytes))
+/// Ensures an image decodes cleanly, applies orientation, and re-encodes to JPEG.
+/// Re-encoding strips uploaded metadata (EXIF/XMP/etc) from the output file.
+/// With `optimize` set, a smaller progressive encoding is used when it helps.
+fn normalize_reader_to_jpeg<R: std::io::BufRead + std::io::Seek>(
+    reader: image::ImageReader<R>,
+    optimize: bool,
+) -> Result<Vec<u8>, HttpetError> {
+    let mut decoder = reader
         .with_guessed_format()
         .map_err(|err| {
             debug!("Failed to guess image format: {}", err);
@@ -621,13 +1118,56 @@ fn normalize_image_to_jpeg(bytes: &[u8]) -> Result<Vec<u8>, HttpetError> {
     image.apply_orientation(orientation);
 
     let mut output = Vec::new();
-    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, 85);
+    let mut encoder =
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, UPLOAD_JPEG_QUALITY);
     encoder
         .encode_image(&image)
         .map_err(|err| HttpetError::InternalServerError(err.to_string()))?;
+    if optimize {
+        return Ok(optimize_jpeg(&image, output));
+    }
     Ok(output)
 }
 
+/// Re-encodes as a progressive JPEG with optimized Huffman tables and tuned
+/// quantization, keeping `baseline` unless the result is smaller.
+fn optimize_jpeg(image: &image::DynamicImage, baseline: Vec<u8>) -> Vec<u8> {
+    let rgb = image.to_rgb8();
+    let (Ok(width), Ok(height)) = (u16::try_from(rgb.width()), u16::try_from(rgb.height())) else {
+        debug!("Image is too large to optimize");
+        return baseline;
+    };
+
+    let mut output = Vec::new();
+    let mut encoder = jpeg_encoder::Encoder::new(&mut output, UPLOAD_JPEG_QUALITY);
+    encoder.set_progressive(true);
+    encoder.set_optimized_huffman_tables(true);
+    encoder.set_quantization_tables(
+        jpeg_encoder::QuantizationTableType::ImageMagick,
+        jpeg_encoder::QuantizationTableType::ImageMagick,
+    );
+    if let Err(err) = encoder.encode(rgb.as_raw(), width, height, jpeg_encoder::ColorType::Rgb) {
+        debug!("Failed to optimize image: {}", err);
+        return baseline;
+    }
+
+    if output.len() >= baseline.len() {
+        info!(
+            "Keeping unoptimized upload, optimized size {} bytes >= {} bytes",
+            output.len(),
+            baseline.len()
+        );
+        return baseline;
+    }
+    info!(
+        "Optimized upload from {} to {} bytes, saved {} bytes",
+        baseline.len(),
+        output.len(),
+        baseline.len() - output.len()
+    );
+    output
+}
+
 /// zips the dates and votes into a series of vote counts
 fn build_vote_series(dates: &[NaiveDate], votes: Option<&HashMap<NaiveDate, i32>>) -> Vec<i32> {
     dates
@@ -663,6 +1203,44 @@ async fn list_pet_images(image_dir: &StdPath, pet_name: &str) -> Result<Vec<Stri
     Ok(images)
 }
 
+/// Counts the JPEG images for each pet in a single blocking scan, rather than
+/// awaiting a directory read per pet.
+async fn count_pet_images(
+    image_dir: &StdPath,
+    pet_names: Vec<String>,
+) -> Result<HashMap<String, usize>, HttpetError> {
+    let image_dir = image_dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut counts = HashMap::with_capacity(pet_names.len());
+        for pet_name in pet_names {
+            let entries = match std::fs::read_dir(image_dir.join(&pet_name)) {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    counts.insert(pet_name, 0);
+                    continue;
+                }
+                Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
+            };
+            let mut count = 0;
+            for entry in entries {
+                let is_jpg = entry?
+                    .path()
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| ext.eq_ignore_ascii_case("jpg"))
+                    .unwrap_or(false);
+                if is_jpg {
+                    count += 1;
+                }
+            }
+            counts.insert(pet_name, count);
+        }
+        Ok(counts)
+    })
+    .await
+    .map_err(|err| HttpetError::InternalServerError(err.to_string()))?
+}
+
 async fn list_image_dirs(image_dir: &StdPath) -> Result<Vec<String>, HttpetError> {
     let mut entries = match tokio::fs::read_dir(image_dir).await {
         Ok(entries) => entries,
@@ -733,6 +1311,52 @@ fn render_vote_chart(pet_name: &str, counts: &[i32]) -> String {
     )
 }
 
+/// Horizontal bars of vote totals per country, busiest first.
+fn render_country_chart(totals: &[(String, i64)]) -> String {
+    if totals.is_empty() {
+        return String::new();
+    }
+    let width = 720.0;
+    let row_height = 24.0;
+    let label_width = 48.0;
+    let padding = 18.0;
+    let height = padding * 2.0 + row_height * totals.len() as f32;
+    let max = totals
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+    let bar_space = width - padding * 2.0 - label_width - 60.0;
+    let bars: Vec<String> = totals
+        .iter()
+        .enumerate()
+        .map(|(idx, (country, count))| {
+            let y = padding + row_height * idx as f32;
+            let bar_width = (*count as f32 / max) * bar_space;
+            let bar_x = padding + label_width;
+            format!(
+                r##"  <text x="{padding}" y="{text_y:.1}" font-size="13" fill="#0f172a">{country}</text>
+  <rect x="{bar_x}" y="{bar_y:.1}" width="{bar_width:.1}" height="{bar_height:.1}" fill="#3b82f6" rx="4" />
+  <text x="{count_x:.1}" y="{text_y:.1}" font-size="13" fill="#64748b">{count}</text>"##,
+                text_y = y + row_height * 0.65,
+                bar_y = y + 4.0,
+                bar_height = row_height - 8.0,
+                count_x = bar_x + bar_width + 8.0,
+                country = html_escape::encode_text(country),
+            )
+        })
+        .collect();
+
+    format!(
+        r##"<svg class="vote-chart" viewBox="0 0 {width} {height}" role="img" aria-label="Votes by country">
+  <rect x="0" y="0" width="{width}" height="{height}" fill="#f8fafc" rx="12" />
+{bars}
+</svg>"##,
+        bars = bars.join("\n"),
+    )
+}
+
 #[allow(clippy::trivially_copy_pass_by_ref)] // so that we can use it in map()
 /// Formats a date as "Mon DD"
 fn format_date(date: &NaiveDate) -> String {
@@ -750,7 +1374,7 @@ mod tests {
         use crate::config::setup_logging;
         let _ = setup_logging(true);
         let jpeg_bytes = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg"));
-        let normalized = normalize_image_to_jpeg(jpeg_bytes).expect("normalize jpeg");
+        let normalized = normalize_image_to_jpeg(jpeg_bytes, false).expect("normalize jpeg");
         assert_eq!(
             image::guess_format(&normalized).expect("guess normalized format"),
             image::ImageFormat::Jpeg
@@ -759,9 +1383,9 @@ mod tests {
         let normalized_image =
             image::load_from_memory(&normalized).expect("decode normalized image");
         assert_eq!(normalized_image.dimensions(), original_image.dimensions());
-        assert!(normalize_image_to_jpeg(&[]).is_err());
-        assert!(normalize_image_to_jpeg(&[0xFF, 0xD8, 0x00, 0xFF, 0xD9]).is_err());
-        assert!(normalize_image_to_jpeg(b"This is not a JPEG file.").is_err());
+        assert!(normalize_image_to_jpeg(&[], false).is_err());
+        assert!(normalize_image_to_jpeg(&[0xFF, 0xD8, 0x00, 0xFF, 0xD9], false).is_err());
+        assert!(normalize_image_to_jpeg(b"This is not a JPEG file.", false).is_err());
     }
 
     #[test]
@@ -770,7 +1394,7 @@ mod tests {
         let with_exif = add_fake_exif_segment(jpeg_bytes);
         assert!(contains_bytes(&with_exif, b"Exif\0\0"));
 
-        let normalized = normalize_image_to_jpeg(&with_exif).expect("normalize exif jpeg");
+        let normalized = normalize_image_to_jpeg(&with_exif, false).expect("normalize exif jpeg");
         assert_eq!(
             image::guess_format(&normalized).expect("guess normalized format"),
             image::ImageFormat::Jpeg
@@ -778,6 +1402,18 @@ mod tests {
         assert!(!contains_bytes(&normalized, b"Exif\0\0"));
     }
 
+    #[test]
+    fn optimized_upload_is_no_larger_and_decodes() {
+        let jpeg_bytes = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg"));
+        let plain = normalize_image_to_jpeg(jpeg_bytes, false).expect("normalize jpeg");
+        let optimized = normalize_image_to_jpeg(jpeg_bytes, true).expect("optimize jpeg");
+
+        assert!(optimized.len() <= plain.len());
+        let original_image = image::load_from_memory(jpeg_bytes).expect("decode original image");
+        let optimized_image = image::load_from_memory(&optimized).expect("decode optimized image");
+        assert_eq!(optimized_image.dimensions(), original_image.dimensions());
+    }
+
     fn add_fake_exif_segment(jpeg_bytes: &[u8]) -> Vec<u8> {
         assert!(jpeg_bytes.starts_with(&[0xFF, 0xD8]));
         let payload = b"Exif\0\0FAKE-EXIF-DATA";
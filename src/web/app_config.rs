@@ -0,0 +1,155 @@
+//! Tunables and feature flags, built once at startup and shared by every request.
+
+use std::path::{Path as StdPath, PathBuf};
+use std::time::Duration;
+
+use url::Url;
+
+use super::admin::UploadFormat;
+use super::images::{ImageFormat, NEGOTIATED_IMAGE_FORMATS};
+use super::middleware::normalize_domain;
+use crate::cli::{CliOptions, FrameOptions};
+use crate::constants::{
+    DEFAULT_MAX_UPLOAD_BYTES, DEFAULT_PROBE_TIMEOUT_SECS, STATUS_CODE_OVERRIDES_PATH, THEMES_DIR,
+};
+
+/// Server configuration. Unlike the rest of [`super::AppState`] none of this changes
+/// while the server runs.
+#[derive(Clone, Debug)]
+pub(crate) struct AppConfig {
+    /// Configured base domains, the first is the primary used for links outside a request.
+    pub(crate) base_domains: Vec<String>,
+    pub(crate) listen_port: u16,
+    pub(crate) frontend_url: Option<Url>,
+    /// Canonical base for links in generated documents like the sitemap.
+    pub(crate) public_base_url_override: Option<Url>,
+    pub(crate) strong_etags: bool,
+    pub(crate) trust_forwarded_proto: bool,
+    pub(crate) trust_forwarded_for: bool,
+    pub(crate) secure_cookies: bool,
+    pub(crate) debug: bool,
+    pub(crate) image_formats: Vec<ImageFormat>,
+    pub(crate) theme: Option<String>,
+    pub(crate) minify_html: bool,
+    pub(crate) frame_options: FrameOptions,
+    pub(crate) optimize_uploads: bool,
+    pub(crate) leaderboard_min_votes: u32,
+    pub(crate) placeholder_images: bool,
+    pub(crate) upload_formats: Vec<UploadFormat>,
+    /// Path-only mode, hosts never select a pet and pet links use paths.
+    pub(crate) disable_subdomains: bool,
+    /// Origins allowed to use the vote endpoints cross-origin, without trailing slashes.
+    pub(crate) cors_origins: Vec<String>,
+    /// Vote submissions must come from one of our own hosts or a CORS origin.
+    pub(crate) check_referer: bool,
+    pub(crate) max_upload_bytes: u64,
+    /// Where admin edits to status code metadata are persisted.
+    pub(crate) status_overrides_path: PathBuf,
+    /// How long `/readyz` waits for the database.
+    pub(crate) probe_timeout: Duration,
+}
+
+impl AppConfig {
+    /// Defaults for everything but where the server is reached.
+    pub(crate) fn new(
+        base_domains: &[impl AsRef<str>],
+        frontend_url: Option<Url>,
+        listen_port: u16,
+    ) -> Self {
+        let mut normalized: Vec<String> = Vec::with_capacity(base_domains.len());
+        for base_domain in base_domains {
+            let base_domain = normalize_domain(base_domain.as_ref().trim().trim_end_matches('/'));
+            if !base_domain.is_empty() && !normalized.contains(&base_domain) {
+                normalized.push(base_domain);
+            }
+        }
+        if normalized.is_empty() {
+            normalized.push("localhost".to_string());
+        }
+
+        Self {
+            base_domains: normalized,
+            listen_port,
+            frontend_url,
+            public_base_url_override: None,
+            strong_etags: false,
+            trust_forwarded_proto: false,
+            trust_forwarded_for: false,
+            secure_cookies: false,
+            debug: false,
+            image_formats: NEGOTIATED_IMAGE_FORMATS.to_vec(),
+            theme: None,
+            minify_html: false,
+            frame_options: FrameOptions::default(),
+            optimize_uploads: false,
+            leaderboard_min_votes: 1,
+            placeholder_images: false,
+            upload_formats: vec![UploadFormat::Jpeg, UploadFormat::Png],
+            disable_subdomains: false,
+            cors_origins: Vec::new(),
+            check_referer: false,
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+            status_overrides_path: PathBuf::from(STATUS_CODE_OVERRIDES_PATH),
+            probe_timeout: Duration::from_secs(DEFAULT_PROBE_TIMEOUT_SECS),
+        }
+    }
+
+    /// The full configuration described by the CLI (and its env vars).
+    pub(crate) fn from_cli(cli: &CliOptions) -> Self {
+        let mut config = Self::new(&cli.base_domain, cli.frontend_url.clone(), cli.port.get());
+        config.apply_cli_options(cli);
+        config
+    }
+
+    /// Applies the optional behaviour flags from the CLI.
+    pub(crate) fn apply_cli_options(&mut self, cli: &CliOptions) {
+        self.strong_etags = cli.strong_etags;
+        self.public_base_url_override = cli.public_base_url_override.clone();
+        self.debug = cli.debug;
+        self.minify_html = !cli.debug && !cli.no_minify_html;
+        self.frame_options = cli.frame_options;
+        self.optimize_uploads = cli.optimize_uploads;
+        self.leaderboard_min_votes = cli.leaderboard_min_votes;
+        self.placeholder_images = cli.placeholder_images;
+        self.upload_formats = cli.upload_formats.clone();
+        self.disable_subdomains = cli.disable_subdomains;
+        self.max_upload_bytes = cli.max_upload_bytes;
+        self.check_referer = cli.check_referer;
+        self.probe_timeout = Duration::from_secs(cli.probe_timeout);
+        self.cors_origins = cli
+            .cors_origins
+            .iter()
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        self.theme = cli.theme.as_deref().and_then(theme_if_available);
+        // keep the server's preference order, the CLI list only bounds it
+        self.image_formats = NEGOTIATED_IMAGE_FORMATS
+            .into_iter()
+            .filter(|format| cli.image_formats.contains(format))
+            .collect();
+        if cli.behind_proxy {
+            self.trust_forwarded_proto = true;
+            self.trust_forwarded_for = true;
+            self.secure_cookies = true;
+        }
+    }
+}
+
+/// Returns the theme name if `static/themes/<theme>.css` exists, otherwise logs
+/// a warning so the default styles are used.
+fn theme_if_available(theme: &str) -> Option<String> {
+    let valid_name = !theme.is_empty()
+        && theme
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if valid_name
+        && StdPath::new(THEMES_DIR)
+            .join(format!("{theme}.css"))
+            .is_file()
+    {
+        return Some(theme.to_string());
+    }
+    tracing::warn!("Theme {theme:?} not found in {THEMES_DIR}, using the default theme");
+    None
+}
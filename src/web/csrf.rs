@@ -1,4 +1,4 @@
-use rand::Rng;
+use rand::RngExt;
 use rand::distr::Alphanumeric;
 use tower_sessions::Session;
 
@@ -6,7 +6,7 @@ use crate::error::HttpetError;
 
 const CSRF_TOKEN_KEY: &str = "csrf_token";
 
-fn generate_token() -> String {
+pub(crate) fn generate_token() -> String {
     rand::rng()
         .sample_iter(&Alphanumeric)
         .take(32)
@@ -7,6 +7,11 @@ const FLASH_FLAG_KEY: &str = "flash_flag";
 pub(crate) const FLASH_UPLOAD_SUCCESS: u16 = 1;
 pub(crate) const FLASH_DELETE_IMAGES_REQUIRED: u16 = 2;
 pub(crate) const FLASH_OVERWRITE_REQUIRED: u16 = 3;
+pub(crate) const FLASH_IMAGES_RELOADED: u16 = 4;
+pub(crate) const FLASH_IMAGE_COPIED: u16 = 5;
+pub(crate) const FLASH_UPLOAD_FORMAT_REJECTED: u16 = 6;
+pub(crate) const FLASH_STATUS_CODE_UPDATED: u16 = 7;
+pub(crate) const FLASH_STATUS_TRANSITION_BLOCKED: u16 = 8;
 
 #[derive(Clone, Debug)]
 pub(crate) struct FlashMessage {
@@ -53,6 +58,26 @@ fn message_for(flag: u16) -> Option<FlashMessage> {
             text: "An image already exists for this status. Confirm overwrite to continue.",
             class: "warning",
         }),
+        FLASH_IMAGES_RELOADED => Some(FlashMessage {
+            text: "Image cache cleared, images will be re-read from disk.",
+            class: "success",
+        }),
+        FLASH_IMAGE_COPIED => Some(FlashMessage {
+            text: "Image copied. It is now available for the target pet and status.",
+            class: "success",
+        }),
+        FLASH_UPLOAD_FORMAT_REJECTED => Some(FlashMessage {
+            text: "That image format isn't accepted for uploads. Allowed formats are set with --upload-formats.",
+            class: "warning",
+        }),
+        FLASH_STATUS_CODE_UPDATED => Some(FlashMessage {
+            text: "Status code text saved. Pages now show the new name and summary.",
+            class: "success",
+        }),
+        FLASH_STATUS_TRANSITION_BLOCKED => Some(FlashMessage {
+            text: "Moving an enabled pet back to submitted hides its images. Tick force to do it anyway.",
+            class: "warning",
+        }),
         _ => None,
     }
 }
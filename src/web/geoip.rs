@@ -0,0 +1,145 @@
+//! Optional country lookups for vote origins, backed by a MaxMind database.
+use std::net::IpAddr;
+use std::path::Path;
+
+use maxminddb::{MaxMindDBError, Reader, geoip2};
+
+use crate::error::HttpetError;
+
+/// A loaded MaxMind country (or city) database.
+pub(crate) struct GeoIp {
+    reader: Reader<Vec<u8>>,
+}
+
+impl std::fmt::Debug for GeoIp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GeoIp")
+            .field("database_type", &self.reader.metadata.database_type)
+            .finish()
+    }
+}
+
+impl GeoIp {
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self, HttpetError> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(bytes)
+    }
+
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Result<Self, HttpetError> {
+        let reader = Reader::from_source(bytes).map_err(|err| {
+            HttpetError::InternalServerError(format!("Failed to load GeoIP database: {err}"))
+        })?;
+        Ok(Self { reader })
+    }
+
+    /// The ISO country code for `ip`, if the database knows it.
+    pub(crate) fn country(&self, ip: IpAddr) -> Option<String> {
+        match self.reader.lookup::<geoip2::Country>(ip) {
+            Ok(record) => record
+                .country
+                .and_then(|country| country.iso_code)
+                .map(str::to_ascii_uppercase),
+            Err(MaxMindDBError::AddressNotFoundError(_)) => None,
+            Err(err) => {
+                tracing::debug!(error=?err, ip=%ip, "GeoIP lookup failed");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    /// Control byte (plus extended type byte) for a MaxMind DB data field.
+    fn field(type_id: u8, size: usize, out: &mut Vec<u8>) {
+        assert!(size < 29, "fixture fields are all small");
+        if type_id <= 7 {
+            out.push((type_id << 5) | size as u8);
+        } else {
+            out.push(size as u8);
+            out.push(type_id - 7);
+        }
+    }
+
+    fn string(value: &str, out: &mut Vec<u8>) {
+        field(2, value.len(), out);
+        out.extend_from_slice(value.as_bytes());
+    }
+
+    fn uint(type_id: u8, value: u64, out: &mut Vec<u8>) {
+        let bytes = value.to_be_bytes();
+        let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+        field(type_id, bytes.len() - skip, out);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+
+    /// Builds a tiny IPv4-only MaxMind database mapping `network/prefix` to `iso_code`,
+    /// every other address is unknown.
+    pub(crate) fn country_db(network: Ipv4Addr, prefix: u8, iso_code: &str) -> Vec<u8> {
+        let node_count = u32::from(prefix);
+        let data_pointer = node_count + 16;
+        let address = u32::from(network);
+
+        // one node per prefix bit, the matching side leads on and the other is empty
+        let mut db = Vec::new();
+        for depth in 0..node_count {
+            let next = if depth + 1 == node_count {
+                data_pointer
+            } else {
+                depth + 1
+            };
+            let mut records = [node_count, node_count];
+            records[((address >> (31 - depth)) & 1) as usize] = next;
+            for record in records {
+                db.extend_from_slice(&record.to_be_bytes()[1..]);
+            }
+        }
+        db.extend_from_slice(&[0; 16]);
+
+        // {"country": {"iso_code": ...}}
+        field(7, 1, &mut db);
+        string("country", &mut db);
+        field(7, 1, &mut db);
+        string("iso_code", &mut db);
+        string(iso_code, &mut db);
+
+        db.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+        field(7, 9, &mut db);
+        string("binary_format_major_version", &mut db);
+        uint(5, 2, &mut db);
+        string("binary_format_minor_version", &mut db);
+        uint(5, 0, &mut db);
+        string("build_epoch", &mut db);
+        uint(9, 0, &mut db);
+        string("database_type", &mut db);
+        string("httpet-test-country", &mut db);
+        string("description", &mut db);
+        field(7, 0, &mut db);
+        string("ip_version", &mut db);
+        uint(5, 4, &mut db);
+        string("languages", &mut db);
+        field(11, 0, &mut db);
+        string("node_count", &mut db);
+        uint(6, u64::from(node_count), &mut db);
+        string("record_size", &mut db);
+        uint(5, 24, &mut db);
+        db
+    }
+
+    #[test]
+    fn fixture_db_resolves_known_network_only() {
+        let geoip = GeoIp::from_bytes(country_db(Ipv4Addr::new(81, 2, 69, 0), 24, "gb"))
+            .expect("load fixture db");
+
+        assert_eq!(
+            geoip.country("81.2.69.160".parse().expect("ip")),
+            Some("GB".to_string())
+        );
+        assert_eq!(geoip.country("81.2.70.1".parse().expect("ip")), None);
+        assert_eq!(geoip.country("192.0.2.1".parse().expect("ip")), None);
+    }
+}
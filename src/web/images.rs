@@ -1,21 +1,330 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use axum::body::Body;
-use axum::http::header::{CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use axum::body::{Body, Bytes};
+use axum::http::header::{
+    ACCEPT, CACHE_CONTROL, CONTENT_TYPE, ETAG, EXPIRES, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, VARY,
+};
 use axum::http::response::Builder;
-use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use axum::response::Response;
 use httpdate::{fmt_http_date, parse_http_date};
+use tokio::sync::RwLock;
+use tracing::error;
+use xxhash_rust::xxh3::xxh3_128;
 
-use crate::constants::IMAGE_CACHE_CONTROL;
+use crate::constants::{IMAGE_CACHE_CONTROL, IMAGE_CACHE_MAX_AGE_SECONDS, IMAGE_CACHE_MAX_BYTES};
 use crate::error::HttpetError;
 
+/// Image encodings that can be served for a status image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImageFormat {
+    /// The canonical format, every stored image has a JPEG.
+    Jpeg,
+    /// Optional pre-rendered variant stored alongside the JPEG.
+    Webp,
+}
+
+impl ImageFormat {
+    /// File extension used on disk.
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+
+    /// Content-Type header value.
+    pub(crate) fn content_type(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+/// Formats considered during negotiation, in server preference order.
+pub(crate) const NEGOTIATED_IMAGE_FORMATS: [ImageFormat; 2] =
+    [ImageFormat::Webp, ImageFormat::Jpeg];
+
+/// Picks the first of `formats` the client explicitly accepts, falling back to JPEG.
+///
+/// Wildcards don't count as support for the optional formats, since `*/*` is
+/// sent by plenty of clients that can only handle JPEG.
+pub(crate) fn negotiate_image_format(headers: &HeaderMap, formats: &[ImageFormat]) -> ImageFormat {
+    let accepted = accepted_media_types(headers);
+
+    formats
+        .iter()
+        .copied()
+        .find(|format| {
+            *format == ImageFormat::Jpeg
+                || accepted
+                    .iter()
+                    .any(|media_type| media_type == format.content_type())
+        })
+        .unwrap_or(ImageFormat::Jpeg)
+}
+
+/// Lowercased media ranges from the Accept header, minus any the client refused with `q=0`.
+pub(crate) fn accepted_media_types(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|entry| {
+            let mut params = entry.split(';');
+            let media_type = params.next()?.trim().to_ascii_lowercase();
+            let rejected = params.any(|param| {
+                param
+                    .trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .is_some_and(|q| q <= 0.0)
+            });
+            (!rejected).then_some(media_type)
+        })
+        .collect()
+}
+
+/// Adds `field` to the response's Vary header, keeping any fields already listed
+/// so each negotiating layer can contribute without clobbering the others.
+pub(crate) fn add_vary(headers: &mut HeaderMap, field: &HeaderName) {
+    let mut fields: Vec<String> = headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+    if fields
+        .iter()
+        .any(|value| value == "*" || value.eq_ignore_ascii_case(field.as_str()))
+    {
+        return;
+    }
+    fields.push(field.as_str().to_string());
+    if let Ok(value) = HeaderValue::from_str(&fields.join(", ")) {
+        headers.insert(VARY, value);
+    }
+}
+
+/// Branded "coming soon" image for enabled pets that don't have any images yet.
+const PLACEHOLDER_SVG: &str = concat!(
+    r##"<svg xmlns="http://www.w3.org/2000/svg" width="600" height="400" viewBox="0 0 600 400">"##,
+    r##"<rect width="600" height="400" fill="#681dd8"/>"##,
+    r##"<text x="300" y="185" fill="#ffffff" font-family="sans-serif" font-size="72" font-weight="bold" text-anchor="middle">httpet</text>"##,
+    r##"<text x="300" y="255" fill="#ab79e0" font-family="sans-serif" font-size="32" text-anchor="middle">Images coming soon</text>"##,
+    "</svg>"
+);
+
+/// Serves the placeholder image, never cached since real images can be uploaded at any time.
+pub(crate) fn placeholder_response() -> Result<Response, HttpetError> {
+    Response::builder()
+        .header(CONTENT_TYPE, "image/svg+xml")
+        .header(CACHE_CONTROL, "no-store")
+        .body(Body::from(PLACEHOLDER_SVG))
+        .map_err(HttpetError::from)
+}
+
+/// A status image loaded from disk, ready to serve.
+#[derive(Clone, Debug)]
+pub(crate) struct CachedImage {
+    pub(crate) bytes: Bytes,
+    metadata: std::fs::Metadata,
+    /// Content ETag, hashed once at load so strong and same-second validators stay cheap.
+    content_etag: Option<HeaderValue>,
+}
+
+impl CachedImage {
+    /// Cache headers for a response built now. The same-second check is redone per
+    /// response, so a file cached in the second it was written isn't stuck ambiguous.
+    pub(crate) fn cache_headers(&self, strong_etags: bool) -> ImageCacheHeaders {
+        self.cache_headers_at(strong_etags, SystemTime::now())
+    }
+
+    fn cache_headers_at(&self, strong_etags: bool, now: SystemTime) -> ImageCacheHeaders {
+        ImageCacheHeaders::for_image_at(
+            &self.metadata,
+            self.content_etag.clone(),
+            strong_etags,
+            now,
+        )
+    }
+
+    /// Whether `metadata` still describes the file this copy was read from.
+    fn is_current(&self, metadata: &std::fs::Metadata) -> bool {
+        self.metadata.len() == metadata.len()
+            && self.metadata.modified().ok() == metadata.modified().ok()
+    }
+}
+
+#[derive(Debug, Default)]
+struct ImageCacheEntries {
+    images: HashMap<PathBuf, (u64, CachedImage)>,
+    bytes: usize,
+    next_insert: u64,
+}
+
+impl ImageCacheEntries {
+    fn remove(&mut self, path: &Path) {
+        if let Some((_, image)) = self.images.remove(path) {
+            self.bytes -= image.bytes.len();
+        }
+    }
+
+    /// Stores `image`, evicting the oldest entries to stay under `max_bytes`.
+    fn insert(&mut self, path: PathBuf, image: CachedImage, max_bytes: usize) {
+        self.remove(&path);
+        if image.bytes.len() > max_bytes {
+            return;
+        }
+        while self.bytes + image.bytes.len() > max_bytes {
+            let Some(oldest) = self
+                .images
+                .iter()
+                .min_by_key(|(_, (inserted, _))| *inserted)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+        self.bytes += image.bytes.len();
+        self.next_insert += 1;
+        self.images.insert(path, (self.next_insert, image));
+    }
+}
+
+/// In-process cache of status image bytes keyed by path.
+///
+/// Every hit is checked against the file's size and mtime, so images synced onto the
+/// volume out of band are picked up on the next request. Missing files aren't cached
+/// and the total size is capped at [`IMAGE_CACHE_MAX_BYTES`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ImageCache {
+    entries: Arc<RwLock<ImageCacheEntries>>,
+    loads: Arc<AtomicUsize>,
+}
+
+impl ImageCache {
+    /// Returns the cached image if the file hasn't changed, otherwise reads it again.
+    pub(crate) async fn get_or_load(
+        &self,
+        image_path: &Path,
+    ) -> Result<Option<CachedImage>, HttpetError> {
+        let Some(metadata) = image_metadata(image_path).await? else {
+            self.entries.write().await.remove(image_path);
+            return Ok(None);
+        };
+        if let Some((_, image)) = self.entries.read().await.images.get(image_path)
+            && image.is_current(&metadata)
+        {
+            return Ok(Some(image.clone()));
+        }
+        self.reload(image_path).await
+    }
+
+    /// Loads the image from disk, replacing any cached entry.
+    pub(crate) async fn reload(
+        &self,
+        image_path: &Path,
+    ) -> Result<Option<CachedImage>, HttpetError> {
+        let image = load_image(image_path).await?;
+        self.loads.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.write().await;
+        match &image {
+            Some(image) => entries.insert(
+                image_path.to_path_buf(),
+                image.clone(),
+                IMAGE_CACHE_MAX_BYTES,
+            ),
+            None => entries.remove(image_path),
+        }
+        Ok(image)
+    }
+
+    /// Drops every cached entry under `dir`.
+    pub(crate) async fn invalidate_dir(&self, dir: &Path) {
+        let mut entries = self.entries.write().await;
+        let stale: Vec<PathBuf> = entries
+            .images
+            .keys()
+            .filter(|path| path.starts_with(dir))
+            .cloned()
+            .collect();
+        for path in stale {
+            entries.remove(&path);
+        }
+    }
+
+    /// Drops every cached entry.
+    pub(crate) async fn clear(&self) {
+        *self.entries.write().await = ImageCacheEntries::default();
+    }
+
+    /// Number of times an image has been loaded from disk.
+    #[cfg(test)]
+    pub(crate) fn loads(&self) -> usize {
+        self.loads.load(Ordering::Relaxed)
+    }
+
+    /// Number of images currently cached.
+    #[cfg(test)]
+    pub(crate) async fn len(&self) -> usize {
+        self.entries.read().await.images.len()
+    }
+}
+
+fn image_read_error(image_path: &Path, err: &std::io::Error) -> HttpetError {
+    error!(
+        "Failed to read image file {}: {}",
+        image_path.display(),
+        err
+    );
+    HttpetError::InternalServerError("Failed to access image, contact an admin!".to_string())
+}
+
+async fn image_metadata(image_path: &Path) -> Result<Option<std::fs::Metadata>, HttpetError> {
+    match tokio::fs::metadata(image_path).await {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(image_read_error(image_path, &err)),
+    }
+}
+
+async fn load_image(image_path: &Path) -> Result<Option<CachedImage>, HttpetError> {
+    let read = async {
+        let metadata = tokio::fs::metadata(image_path).await?;
+        let bytes = tokio::fs::read(image_path).await?;
+        Ok::<_, std::io::Error>((metadata, bytes))
+    };
+    match read.await {
+        Ok((metadata, bytes)) => Ok(Some(CachedImage {
+            content_etag: build_strong_etag(&bytes),
+            bytes: Bytes::from(bytes),
+            metadata,
+        })),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(image_read_error(image_path, &err)),
+    }
+}
+
 /// Cache headers derived from image metadata.
 #[derive(Clone, Debug)]
 pub(crate) struct ImageCacheHeaders {
     etag: Option<HeaderValue>,
     last_modified: Option<HeaderValue>,
     modified_at: Option<SystemTime>,
+    /// The file was modified in the same second it was read, so the second-precision
+    /// `Last-Modified` can't tell it apart from another write later in that second.
+    last_modified_ambiguous: bool,
 }
 
 impl ImageCacheHeaders {
@@ -29,7 +338,46 @@ impl ImageCacheHeaders {
             etag,
             last_modified,
             modified_at,
+            last_modified_ambiguous: false,
+        }
+    }
+
+    /// Builds cache headers for an image read from disk.
+    ///
+    /// Per RFC 9110 section 8.8.2.2, a modification time within the current second
+    /// isn't a reliable validator, so those images get a content ETag and
+    /// `If-Modified-Since` alone won't produce a 304 for them.
+    pub(crate) fn for_image(
+        metadata: &std::fs::Metadata,
+        content: &[u8],
+        strong_etags: bool,
+    ) -> Self {
+        Self::for_image_at(
+            metadata,
+            build_strong_etag(content),
+            strong_etags,
+            SystemTime::now(),
+        )
+    }
+
+    fn for_image_at(
+        metadata: &std::fs::Metadata,
+        content_etag: Option<HeaderValue>,
+        strong_etags: bool,
+        now: SystemTime,
+    ) -> Self {
+        let ambiguous = metadata
+            .modified()
+            .ok()
+            .and_then(unix_seconds)
+            .zip(unix_seconds(now))
+            .is_some_and(|(modified, now)| modified >= now);
+        let mut headers = Self::from_metadata(metadata);
+        if strong_etags || ambiguous {
+            headers.etag = content_etag;
         }
+        headers.last_modified_ambiguous = ambiguous;
+        headers
     }
 
     /// Returns the ETag header value, if available.
@@ -44,8 +392,12 @@ impl ImageCacheHeaders {
 }
 
 /// Applies image cache headers to a response builder.
+///
+/// `Expires` mirrors the `Cache-Control` max-age for caches that only understand HTTP/1.0.
 pub(crate) fn apply_cache_headers(mut builder: Builder, cache: &ImageCacheHeaders) -> Builder {
     builder = builder.header(CACHE_CONTROL, IMAGE_CACHE_CONTROL.as_str());
+    let expires = SystemTime::now() + Duration::from_secs(IMAGE_CACHE_MAX_AGE_SECONDS);
+    builder = builder.header(EXPIRES, fmt_http_date(expires));
     if let Some(etag) = cache.etag() {
         builder = builder.header(ETAG, etag.clone());
     }
@@ -55,8 +407,23 @@ pub(crate) fn apply_cache_headers(mut builder: Builder, cache: &ImageCacheHeader
     builder
 }
 
+/// Returns true when the request asked to bypass caches with `Cache-Control: no-cache`.
+pub(crate) fn request_is_no_cache(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-cache"))
+}
+
 /// Returns true when the request matches a not-modified response.
+///
+/// Requests carrying `Cache-Control: no-cache` always get the full body.
 pub(crate) fn is_not_modified(headers: &HeaderMap, cache: &ImageCacheHeaders) -> bool {
+    if request_is_no_cache(headers) {
+        return false;
+    }
     if let Some(if_none_match) = headers.get(IF_NONE_MATCH) {
         if let Ok(value) = if_none_match.to_str() {
             let value = value.trim();
@@ -72,11 +439,14 @@ pub(crate) fn is_not_modified(headers: &HeaderMap, cache: &ImageCacheHeaders) ->
         return false;
     }
 
-    if let (Some(if_modified_since), Some(modified_at)) =
-        (headers.get(IF_MODIFIED_SINCE), cache.modified_at)
+    // Last-Modified is sent with second precision, so compare at that precision.
+    if let (Some(if_modified_since), Some(modified_at)) = (
+        headers.get(IF_MODIFIED_SINCE),
+        cache.modified_at.and_then(unix_seconds),
+    ) && !cache.last_modified_ambiguous
         && let Ok(value) = if_modified_since.to_str()
         && let Ok(since) = parse_http_date(value)
-        && modified_at <= since
+        && unix_seconds(since).is_some_and(|since| modified_at <= since)
     {
         return true;
     }
@@ -91,6 +461,12 @@ pub(crate) fn not_modified_response(cache: &ImageCacheHeaders) -> Result<Respons
     builder.body(Body::empty()).map_err(HttpetError::from)
 }
 
+fn unix_seconds(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
 fn build_etag(size: u64, modified_at: Option<SystemTime>) -> Option<HeaderValue> {
     let suffix = match modified_at {
         Some(modified) => modified
@@ -102,3 +478,222 @@ fn build_etag(size: u64, modified_at: Option<SystemTime>) -> Option<HeaderValue>
     let value = format!("W/\"{}-{}\"", size, suffix);
     HeaderValue::from_str(&value).ok()
 }
+
+fn build_strong_etag(content: &[u8]) -> Option<HeaderValue> {
+    HeaderValue::from_str(&format!("\"{:032x}\"", xxh3_128(content))).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strong_etags_differ_for_same_size_and_mtime() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let first_path = dir.path().join("first.jpg");
+        let second_path = dir.path().join("second.jpg");
+        std::fs::write(&first_path, [0xFF, 0xD8, 0x01, 0xFF, 0xD9]).expect("write first");
+        std::fs::write(&second_path, [0xFF, 0xD8, 0x02, 0xFF, 0xD9]).expect("write second");
+
+        let modified = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        for path in [&first_path, &second_path] {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .expect("open file")
+                .set_modified(modified)
+                .expect("set mtime");
+        }
+        let first_metadata = std::fs::metadata(&first_path).expect("first metadata");
+        let second_metadata = std::fs::metadata(&second_path).expect("second metadata");
+
+        let first_weak = ImageCacheHeaders::from_metadata(&first_metadata);
+        let second_weak = ImageCacheHeaders::from_metadata(&second_metadata);
+        assert_eq!(first_weak.etag(), second_weak.etag());
+
+        let first_strong = ImageCacheHeaders::for_image(
+            &first_metadata,
+            &std::fs::read(&first_path).expect("read first"),
+            true,
+        );
+        let second_strong = ImageCacheHeaders::for_image(
+            &second_metadata,
+            &std::fs::read(&second_path).expect("read second"),
+            true,
+        );
+        let first_etag = first_strong.etag().expect("first etag");
+        let second_etag = second_strong.etag().expect("second etag");
+        assert_ne!(first_etag, second_etag);
+        assert!(!first_etag.to_str().expect("etag str").starts_with("W/"));
+        assert_eq!(first_strong.last_modified(), first_weak.last_modified());
+    }
+
+    #[test]
+    fn same_second_modification_avoids_false_not_modified() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("200.jpg");
+        let set_mtime = |modified: SystemTime| {
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .expect("open file")
+                .set_modified(modified)
+                .expect("set mtime");
+        };
+        let second = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let now = second + std::time::Duration::from_millis(900);
+
+        let first_content = [0xFF, 0xD8, 0x01, 0xFF, 0xD9];
+        std::fs::write(&path, first_content).expect("write first");
+        set_mtime(second + std::time::Duration::from_millis(200));
+        let first = ImageCacheHeaders::for_image_at(
+            &std::fs::metadata(&path).expect("first metadata"),
+            build_strong_etag(&first_content),
+            false,
+            now,
+        );
+
+        let second_content = [0xFF, 0xD8, 0x02, 0xFF, 0xD9];
+        std::fs::write(&path, second_content).expect("write second");
+        set_mtime(second + std::time::Duration::from_millis(700));
+        let rewritten = ImageCacheHeaders::for_image_at(
+            &std::fs::metadata(&path).expect("second metadata"),
+            build_strong_etag(&second_content),
+            false,
+            now,
+        );
+        assert_eq!(first.last_modified(), rewritten.last_modified());
+
+        let mut request = HeaderMap::new();
+        request.insert(
+            IF_MODIFIED_SINCE,
+            first.last_modified().expect("last modified").clone(),
+        );
+        assert!(!is_not_modified(&request, &rewritten));
+        request.insert(IF_NONE_MATCH, first.etag().expect("etag").clone());
+        assert!(!is_not_modified(&request, &rewritten));
+
+        // Once the second has passed the usual validators apply again.
+        let later = ImageCacheHeaders::for_image_at(
+            &std::fs::metadata(&path).expect("second metadata"),
+            build_strong_etag(&second_content),
+            false,
+            second + std::time::Duration::from_secs(5),
+        );
+        assert!(
+            later
+                .etag()
+                .and_then(|etag| etag.to_str().ok())
+                .is_some_and(|etag| etag.starts_with("W/"))
+        );
+        let mut request = HeaderMap::new();
+        request.insert(
+            IF_MODIFIED_SINCE,
+            later.last_modified().expect("last modified").clone(),
+        );
+        assert!(is_not_modified(&request, &later));
+    }
+
+    #[tokio::test]
+    async fn cached_image_rechecks_same_second_modification_per_response() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("200.jpg");
+        std::fs::write(&path, [0xFF, 0xD8, 0x01, 0xFF, 0xD9]).expect("write image");
+        let second = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .expect("open file")
+            .set_modified(second + std::time::Duration::from_millis(200))
+            .expect("set mtime");
+        let image = load_image(&path)
+            .await
+            .expect("load image")
+            .expect("image exists");
+
+        let fresh = image.cache_headers_at(false, second + std::time::Duration::from_millis(900));
+        assert!(fresh.last_modified_ambiguous);
+        let later = image.cache_headers_at(false, second + std::time::Duration::from_secs(5));
+        assert!(!later.last_modified_ambiguous);
+        assert!(
+            later
+                .etag()
+                .and_then(|etag| etag.to_str().ok())
+                .is_some_and(|etag| etag.starts_with("W/"))
+        );
+    }
+
+    #[tokio::test]
+    async fn image_cache_evicts_oldest_past_byte_limit() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let mut entries = ImageCacheEntries::default();
+        for name in ["first", "second", "third"] {
+            let path = dir.path().join(format!("{name}.jpg"));
+            std::fs::write(&path, [0u8; 10]).expect("write image");
+            let image = load_image(&path)
+                .await
+                .expect("load image")
+                .expect("image exists");
+            entries.insert(path, image, 25);
+        }
+        assert_eq!(entries.bytes, 20);
+        assert!(!entries.images.contains_key(&dir.path().join("first.jpg")));
+        assert!(entries.images.contains_key(&dir.path().join("third.jpg")));
+
+        let path = dir.path().join("huge.jpg");
+        std::fs::write(&path, [0u8; 30]).expect("write image");
+        let image = load_image(&path)
+            .await
+            .expect("load image")
+            .expect("image exists");
+        entries.insert(path.clone(), image, 25);
+        assert!(!entries.images.contains_key(&path));
+        assert_eq!(entries.bytes, 20);
+    }
+
+    #[test]
+    fn negotiate_image_format_requires_explicit_webp() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            negotiate_image_format(&headers, &NEGOTIATED_IMAGE_FORMATS),
+            ImageFormat::Jpeg
+        );
+
+        headers.insert(ACCEPT, HeaderValue::from_static("image/*,*/*;q=0.8"));
+        assert_eq!(
+            negotiate_image_format(&headers, &NEGOTIATED_IMAGE_FORMATS),
+            ImageFormat::Jpeg
+        );
+
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("image/avif,image/webp,*/*;q=0.8"),
+        );
+        assert_eq!(
+            negotiate_image_format(&headers, &NEGOTIATED_IMAGE_FORMATS),
+            ImageFormat::Webp
+        );
+
+        headers.insert(ACCEPT, HeaderValue::from_static("image/webp;q=0,*/*"));
+        assert_eq!(
+            negotiate_image_format(&headers, &NEGOTIATED_IMAGE_FORMATS),
+            ImageFormat::Jpeg
+        );
+    }
+
+    #[test]
+    fn add_vary_merges_without_duplicates() {
+        let mut headers = HeaderMap::new();
+        add_vary(&mut headers, &ACCEPT);
+        assert_eq!(headers.get(VARY).expect("vary"), "accept");
+
+        headers.insert(VARY, HeaderValue::from_static("Accept-Encoding"));
+        add_vary(&mut headers, &ACCEPT);
+        add_vary(&mut headers, &ACCEPT);
+        assert_eq!(headers.get(VARY).expect("vary"), "Accept-Encoding, accept");
+
+        headers.insert(VARY, HeaderValue::from_static("*"));
+        add_vary(&mut headers, &ACCEPT);
+        assert_eq!(headers.get(VARY).expect("vary"), "*");
+    }
+}
@@ -1,37 +1,69 @@
 use axum::body::Body;
 use axum::extract::{ConnectInfo, FromRequestParts};
-use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE, HOST, TRANSFER_ENCODING};
+use axum::http::header::{
+    ACCEPT, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+    ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ALLOW,
+    CONTENT_LENGTH, CONTENT_SECURITY_POLICY, CONTENT_TYPE, HOST, ORIGIN, REFERER,
+    TRANSFER_ENCODING, X_FRAME_OPTIONS,
+};
 use axum::http::request::Parts;
-use axum::http::{HeaderMap, Request};
+use axum::http::{HeaderMap, Method, Request};
 use axum::middleware::Next;
 use axum::response::{Redirect, Response};
 use chrono::{SecondsFormat, Utc};
 use serde::Serialize;
+use url::Url;
 
 use std::net::{IpAddr, SocketAddr};
 
+use super::images::{accepted_media_types, add_vary};
 use super::prelude::*;
 use super::{AppState, normalize_pet_name, views};
+use crate::constants::{
+    VOTE_CORS_ALLOW_HEADERS, VOTE_CORS_ALLOW_METHODS, VOTE_CORS_MAX_AGE_SECONDS,
+};
+use crate::error::{NoPetsEnabled, NotFoundReason};
 
 #[derive(Debug, Clone, Serialize)]
 pub(crate) struct AnimalDomain {
     pub(crate) animal: Option<String>,
+    /// The configured base domain the request's host belongs to.
+    #[serde(skip)]
+    pub(crate) base_domain: String,
 }
 
 impl AnimalDomain {
-    fn from_host(base_domain: &str, host: &str) -> Self {
-        let host = host
-            .split(':')
-            .next()
-            .unwrap_or(host)
-            .trim_end_matches('.')
-            .to_ascii_lowercase();
-        let animal = animal_from_host(base_domain, &host);
+    fn from_host(state: &AppState, host: &str) -> Self {
+        let host = normalize_host(host);
+        let base_domain = state.base_domain_for_host(&host).to_string();
+        let animal = if state.config.disable_subdomains {
+            None
+        } else {
+            animal_from_host(&base_domain, &host)
+        };
 
-        Self { animal }
+        Self {
+            animal,
+            base_domain,
+        }
     }
 }
 
+/// Finds the configured base domain a normalized `host` is on, preferring the
+/// longest match so nested domains resolve to the most specific one.
+pub(crate) fn matching_base_domain<'a>(base_domains: &'a [String], host: &str) -> Option<&'a str> {
+    base_domains
+        .iter()
+        .map(String::as_str)
+        .filter(|base_domain| {
+            host == *base_domain
+                || host
+                    .strip_suffix(base_domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        })
+        .max_by_key(|base_domain| base_domain.len())
+}
+
 fn animal_from_host(base_domain: &str, host: &str) -> Option<String> {
     let www_domain = format!("www.{}", base_domain);
 
@@ -59,12 +91,12 @@ impl FromRequestParts<AppState> for AnimalDomain {
         let host = parts
             .headers
             .get(HOST)
-            .and_then(|value| value.to_str().ok())
+            .and_then(host_header_str)
             .map(str::to_owned);
 
         async move {
             let host = host.ok_or((StatusCode::BAD_REQUEST, "Missing Host header"))?;
-            Ok(Self::from_host(&state.base_domain, &host))
+            Ok(Self::from_host(state, &host))
         }
     }
 }
@@ -77,31 +109,70 @@ pub(crate) async fn admin_base_domain_only(
     let host = request
         .headers()
         .get(HOST)
-        .and_then(|value| value.to_str().ok())
+        .and_then(host_header_str)
         .unwrap_or_default();
     let host = normalize_host(host);
 
-    if host == state.base_domain {
+    if state.config.base_domains.contains(&host) {
         return next.run(request).await;
     }
 
     let uri = request.uri().to_string();
-    let target = format!("{}{}", state.base_url(), uri);
+    let mut base_url = state.base_url_for(state.base_domain_for_host(&host));
+    if state.config.trust_forwarded_proto
+        && let Some(proto) = forwarded_proto(request.headers())
+        && let Some((_scheme, rest)) = base_url.split_once("://")
+    {
+        base_url = format!("{proto}://{rest}");
+    }
+    let target = format!("{}{}", base_url, uri);
     Redirect::to(&target).into_response()
 }
 
+/// Reads the scheme reported by a reverse proxy, only accepting http/https.
+fn forwarded_proto(headers: &HeaderMap) -> Option<&'static str> {
+    let value = headers.get("x-forwarded-proto")?.to_str().ok()?;
+    // proxies chaining the header append values, the first is the client-facing one
+    match value
+        .split(',')
+        .next()?
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "https" => Some("https"),
+        "http" => Some("http"),
+        _ => None,
+    }
+}
+
 pub(crate) async fn not_found_template(
     State(state): State<AppState>,
     request: Request<Body>,
     next: Next,
 ) -> Response {
+    let wants_json = accepted_media_types(request.headers())
+        .iter()
+        .any(|media_type| media_type == "application/json");
+    let base_domain = request_base_domain(&state, request.headers()).to_string();
     let response = next.run(request).await;
     if response.status() != StatusCode::NOT_FOUND {
         return response;
     }
 
     let (parts, _body) = response.into_parts();
-    let mut not_found = views::not_found_response(&state).await;
+    // reasons can include filesystem details, so they're only exposed when debugging
+    let reason = parts
+        .extensions
+        .get::<NotFoundReason>()
+        .filter(|_| state.config.debug)
+        .map(|reason| reason.0.clone());
+    let no_pets = parts.extensions.get::<NoPetsEnabled>().is_some();
+    let mut not_found = if wants_json {
+        not_found_json(reason, no_pets)
+    } else {
+        views::not_found_response(&state, &base_domain, reason, no_pets).await
+    };
     let headers = not_found.headers_mut();
     for (name, value) in parts.headers.iter() {
         if name == CONTENT_TYPE || name == CONTENT_LENGTH || name == TRANSFER_ENCODING {
@@ -109,14 +180,189 @@ pub(crate) async fn not_found_template(
         }
         headers.append(name, value.clone());
     }
+    add_vary(headers, &ACCEPT);
 
     not_found
 }
 
-pub(crate) async fn request_logger(request: Request<Body>, next: Next) -> Response {
+/// 404 body for API clients, the reason is embedded as JSON when it parses as such.
+fn not_found_json(reason: Option<String>, no_pets: bool) -> Response {
+    let error = if no_pets {
+        "no_pets_enabled"
+    } else {
+        "not_found"
+    };
+    let mut body = serde_json::json!({"error": error});
+    if let Some(reason) = reason {
+        body["reason"] = serde_json::from_str(&reason).unwrap_or(serde_json::Value::String(reason));
+    }
+    (StatusCode::NOT_FOUND, axum::Json(body)).into_response()
+}
+
+/// Adds anti-framing headers to pages that shouldn't be embedded, such as admin and vote forms.
+pub(crate) async fn frame_options(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    if let Some((frame_options, csp)) = state.config.frame_options.header_values() {
+        let headers = response.headers_mut();
+        headers.insert(X_FRAME_OPTIONS, HeaderValue::from_static(frame_options));
+        if !headers.contains_key(CONTENT_SECURITY_POLICY) {
+            headers.insert(CONTENT_SECURITY_POLICY, HeaderValue::from_static(csp));
+        }
+    }
+    response
+}
+
+/// The request's `Origin` when it's one of the configured CORS origins.
+fn allowed_cors_origin(state: &AppState, headers: &HeaderMap) -> Option<HeaderValue> {
+    let origin = headers.get(ORIGIN)?;
+    let origin_str = origin.to_str().ok()?;
+    state
+        .config
+        .cors_origins
+        .iter()
+        .any(|allowed| allowed == origin_str)
+        .then(|| origin.clone())
+}
+
+/// Lets configured origins read vote responses, sending the session cookie along
+/// since voting needs its CSRF token.
+pub(crate) async fn vote_cors(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let origin = allowed_cors_origin(&state, request.headers());
+    let mut response = next.run(request).await;
+    if !state.config.cors_origins.is_empty() {
+        add_vary(response.headers_mut(), &ORIGIN);
+    }
+    if let Some(origin) = origin {
+        let headers = response.headers_mut();
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(
+            ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+    response
+}
+
+/// With `--check-referer`, turns away vote submissions whose `Origin` (or `Referer` when
+/// there's no `Origin`) isn't one of our hosts or a configured CORS origin.
+pub(crate) async fn vote_referer_check(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !state.config.check_referer || request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    match vote_source_allowed(&state, request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(err) => err.into_response(),
+    }
+}
+
+fn vote_source_allowed(state: &AppState, headers: &HeaderMap) -> Result<(), HttpetError> {
+    let (header, value) = match headers.get(ORIGIN) {
+        Some(value) => (ORIGIN, value),
+        None => (
+            REFERER,
+            headers
+                .get(REFERER)
+                .ok_or_else(|| HttpetError::Forbidden("vote without Origin or Referer".into()))?,
+        ),
+    };
+    let value = value.to_str().unwrap_or_default();
+    let url = Url::parse(value)
+        .map_err(|_| HttpetError::Forbidden(format!("unparseable {header} {value:?}")))?;
+    let origin = url.origin().ascii_serialization();
+    if state.config.cors_origins.contains(&origin) {
+        return Ok(());
+    }
+    let host = url.host_str().map(normalize_host).unwrap_or_default();
+    if matching_base_domain(&state.config.base_domains, &host).is_some() {
+        return Ok(());
+    }
+    Err(HttpetError::Forbidden(format!(
+        "vote from foreign {header} {value:?}"
+    )))
+}
+
+/// Answers CORS preflight requests for the vote endpoints. Origins that aren't
+/// configured get no CORS headers, so the browser blocks the real request.
+pub(crate) async fn vote_preflight(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    let response_headers = response.headers_mut();
+    response_headers.insert(ALLOW, HeaderValue::from_static(VOTE_CORS_ALLOW_METHODS));
+    if allowed_cors_origin(&state, &headers).is_some() {
+        response_headers.insert(
+            ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static(VOTE_CORS_ALLOW_METHODS),
+        );
+        response_headers.insert(
+            ACCESS_CONTROL_ALLOW_HEADERS,
+            HeaderValue::from_static(VOTE_CORS_ALLOW_HEADERS),
+        );
+        response_headers.insert(
+            ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from(VOTE_CORS_MAX_AGE_SECONDS),
+        );
+    }
+    response
+}
+
+/// Minifies rendered HTML responses when enabled. Whitespace inside `<pre>`,
+/// `<textarea>` and `<script>` is left alone.
+pub(crate) async fn minify_html_response(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if !state.config.minify_html || !is_html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            return HttpetError::InternalServerError(err.to_string()).into_response();
+        }
+    };
+    let cfg = minify_html::Cfg {
+        keep_closing_tags: true,
+        keep_html_and_head_opening_tags: true,
+        ..minify_html::Cfg::spec_compliant()
+    };
+    let minified = minify_html::minify(&bytes, &cfg);
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(minified))
+}
+
+/// The client address `request_logger` settled on, honouring `X-Forwarded-For` only
+/// when the proxy is trusted. `None` when it isn't known.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ClientIp(pub(crate) Option<IpAddr>);
+
+pub(crate) async fn request_logger(
+    State(state): State<AppState>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
     let method = request.method().to_string();
     let uri = request.uri().to_string();
-    let client_ip = client_ip_from_request(&request);
+    let mut client_ip = client_ip_from_request(&request);
     let timestamp = current_timestamp();
 
     let headers = request.headers();
@@ -138,6 +384,11 @@ pub(crate) async fn request_logger(request: Request<Body>, next: Next) -> Respon
             return response;
         }
     };
+    if state.config.trust_forwarded_for
+        && let Some(forwarded_ip) = forwarded_client_ip(forwarded_for.as_deref())
+    {
+        client_ip = forwarded_ip.to_string();
+    }
     let real_ip = match parse_real_ip_header(headers, &client_ip) {
         Ok(value) => value,
         Err(err) => {
@@ -157,6 +408,9 @@ pub(crate) async fn request_logger(request: Request<Body>, next: Next) -> Respon
         }
     };
 
+    request
+        .extensions_mut()
+        .insert(ClientIp(client_ip.parse().ok()));
     let response = next.run(request).await;
     let status = response.status().as_u16();
     RequestLog::new(
@@ -168,16 +422,38 @@ pub(crate) async fn request_logger(request: Request<Body>, next: Next) -> Respon
         forwarded_for,
         real_ip,
     )
+    .with_image(response.extensions().get::<ImageRequest>())
     .print();
     response
 }
 
-fn normalize_host(host: &str) -> String {
-    host.split(':')
-        .next()
-        .unwrap_or(host)
-        .trim_end_matches('.')
-        .to_ascii_lowercase()
+/// The configured base domain a request was sent to, the primary when there's no usable Host.
+pub(crate) fn request_base_domain<'a>(state: &'a AppState, headers: &HeaderMap) -> &'a str {
+    state.base_domain_for_host(
+        headers
+            .get(HOST)
+            .and_then(host_header_str)
+            .unwrap_or_default(),
+    )
+}
+
+/// A `Host` header as text. Unlike `to_str` this accepts UTF-8, so a client sending
+/// an IDN without punycoding it still reaches the pet.
+fn host_header_str(value: &HeaderValue) -> Option<&str> {
+    std::str::from_utf8(value.as_bytes()).ok()
+}
+
+/// Reduces a `Host` header to the form base domains are stored in: no port, no
+/// trailing dots, lowercase and punycoded.
+pub(crate) fn normalize_host(host: &str) -> String {
+    normalize_domain(host.split(':').next().unwrap_or(host))
+}
+
+/// Strips every trailing dot and converts IDNs to lowercase ASCII (`Bücher.example.`
+/// becomes `xn--bcher-kva.example`). Names IDNA rejects are only lowercased.
+pub(crate) fn normalize_domain(domain: &str) -> String {
+    let domain = domain.trim_end_matches('.');
+    idna::domain_to_ascii(domain).unwrap_or_else(|_| domain.to_ascii_lowercase())
 }
 
 fn client_ip_from_request(request: &Request<Body>) -> String {
@@ -187,6 +463,12 @@ fn client_ip_from_request(request: &Request<Body>) -> String {
     "unknown".to_string()
 }
 
+/// The address our immediate peer (the trusted proxy) saw, which is the last
+/// `X-Forwarded-For` entry. Earlier entries are client-supplied and can't be trusted.
+fn forwarded_client_ip(forwarded_for: Option<&[IpAddr]>) -> Option<IpAddr> {
+    forwarded_for?.last().copied()
+}
+
 fn current_timestamp() -> String {
     Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
 }
@@ -203,6 +485,18 @@ struct RequestLog<'a> {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     real_ip: Option<IpAddr>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pet: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status_code: Option<u16>,
+}
+
+/// Response extension set by image handlers so the request log records which image was served.
+#[derive(Clone, Debug)]
+pub(crate) struct ImageRequest {
+    pub(crate) pet: String,
+    pub(crate) status_code: u16,
 }
 
 impl<'a> RequestLog<'a> {
@@ -223,9 +517,20 @@ impl<'a> RequestLog<'a> {
             status,
             forwarded_for,
             real_ip,
+            pet: None,
+            status_code: None,
         }
     }
 
+    /// Adds the pet and status code when the response served an image.
+    fn with_image(mut self, image: Option<&'a ImageRequest>) -> Self {
+        if let Some(image) = image {
+            self.pet = Some(&image.pet);
+            self.status_code = Some(image.status_code);
+        }
+        self
+    }
+
     pub(crate) fn print(&self) {
         tracing::log::info!("{}", serde_json::json!(&self));
     }
@@ -324,6 +629,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn image_requests_log_pet_and_status_code() {
+        let image = ImageRequest {
+            pet: "dog".to_string(),
+            status_code: 404,
+        };
+        let log = RequestLog::new(
+            "2026-02-03T12:34:56.789Z",
+            "203.0.113.42",
+            "GET",
+            "/404",
+            200,
+            None,
+            None,
+        )
+        .with_image(Some(&image));
+        let value = serde_json::json!(&log);
+        assert_eq!(value["pet"], "dog");
+        assert_eq!(value["status_code"], 404);
+        assert_eq!(value["status"], 200);
+
+        let value = serde_json::json!(&log.with_image(None));
+        assert_eq!(value["pet"], "dog");
+
+        let plain = RequestLog::new(
+            "2026-02-03T12:34:56.789Z",
+            "203.0.113.42",
+            "GET",
+            "/about",
+            200,
+            None,
+            None,
+        );
+        let value = serde_json::json!(&plain);
+        assert!(value.get("pet").is_none());
+        assert!(value.get("status_code").is_none());
+    }
+
     #[test]
     fn client_ip_ignores_forwarded_headers() {
         let mut request = Request::builder()
@@ -364,6 +707,30 @@ mod tests {
         assert_eq!(client_ip_from_request(&request), "unknown");
     }
 
+    #[test]
+    fn forwarded_client_ip_uses_last_hop() {
+        let forwarded = [
+            IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1)),
+            IpAddr::V4(Ipv4Addr::new(198, 51, 100, 2)),
+        ];
+        assert_eq!(
+            forwarded_client_ip(Some(&forwarded)),
+            Some(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 2)))
+        );
+        assert_eq!(forwarded_client_ip(Some(&[])), None);
+        assert_eq!(forwarded_client_ip(None), None);
+    }
+
+    #[test]
+    fn forwarded_proto_accepts_first_known_scheme() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(forwarded_proto(&headers), None);
+        headers.insert("x-forwarded-proto", "HTTPS, http".parse().expect("header"));
+        assert_eq!(forwarded_proto(&headers), Some("https"));
+        headers.insert("x-forwarded-proto", "gopher".parse().expect("header"));
+        assert_eq!(forwarded_proto(&headers), None);
+    }
+
     #[test]
     fn parse_forwarded_for_header_accepts_valid_ips() {
         let request = Request::builder()
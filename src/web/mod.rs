@@ -3,111 +3,236 @@
 use std::net::SocketAddr;
 use std::path::{Path as StdPath, PathBuf};
 use std::str::FromStr;
+use std::time::Instant;
 
 use crate::cli::CliOptions;
-use crate::constants::{CSRF_SESSION_LENGTH, IMAGE_DIR, X_HTTPET_ANIMAL};
-use crate::db::entities::pets;
-use axum::Router;
+use crate::constants::{
+    ALL_TIME_TOP_PETS_CACHE_SECONDS, CSRF_SESSION_LENGTH, IMAGE_DIR,
+    NOT_FOUND_POOL_REFRESH_SECONDS, PENDING_UPLOAD_MAX_AGE_SECONDS, PENDING_UPLOAD_SWEEP_SECONDS,
+    X_HTTPET_ANIMAL,
+};
+use crate::db::entities::{pets, votes};
+use crate::status_codes::{self, StatusCodes, StatusInfo, StatusSlugs};
 use axum::extract::DefaultBodyLimit;
 use axum::http::HeaderMap;
+use axum::http::header::ACCEPT;
 use axum::response::Redirect;
+use axum::{Extension, Router};
 use rand::prelude::IndexedRandom;
 use sea_orm::{DatabaseTransaction, IntoActiveModel, TransactionTrait};
 use serde::Deserialize;
 use serde_json::json;
 use time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tower_http::services::ServeDir;
 use tower_sessions::session::Expiry;
 use tower_sessions::{MemoryStore, Session, SessionManagerLayer};
 
 mod admin;
+mod app_config;
 mod csrf;
 mod flash;
+mod geoip;
 mod images;
 mod middleware;
+mod pending_upload;
 mod prelude;
+mod probes;
+mod spooled_upload;
 mod views;
 
 use prelude::*;
 
 use admin::{
     admin_handler, admin_pet_image_handler, admin_pet_upload_view, admin_pet_view,
-    create_pet_handler, delete_pet_post, delete_pet_view, update_pet_handler, upload_image_handler,
+    admin_status_code_view, cancel_pending_upload, confirm_pending_upload, copy_image_handler,
+    create_pet_handler, delete_pet_post, delete_pet_view, onboard_pet_handler,
+    pending_upload_image, reload_images_handler, update_pet_handler, update_status_code_handler,
+    upload_image_handler,
 };
+use app_config::AppConfig;
 use csrf::validate_csrf;
-use images::{ImageCacheHeaders, apply_cache_headers, is_not_modified, not_modified_response};
-use middleware::{AnimalDomain, admin_base_domain_only, not_found_template, request_logger};
+use geoip::GeoIp;
+use images::{
+    ImageCache, NEGOTIATED_IMAGE_FORMATS, add_vary, apply_cache_headers, is_not_modified,
+    negotiate_image_format, not_modified_response, placeholder_response, request_is_no_cache,
+};
+use middleware::{
+    AnimalDomain, ClientIp, ImageRequest, admin_base_domain_only, frame_options,
+    matching_base_domain, minify_html_response, normalize_host, not_found_template,
+    request_base_domain, request_logger, vote_cors, vote_preflight, vote_referer_check,
+};
 use url::Url;
-use views::{VotePageTemplate, VoteThanksTemplate};
+use views::{TopPet, VotePageTemplate, VoteThanksTemplate};
+
+pub use admin::UploadFormat;
+pub use images::ImageFormat;
 
 #[derive(Clone, Debug)]
 pub(crate) struct AppState {
-    base_domain: String,
+    config: Arc<AppConfig>,
     enabled_pets: Arc<RwLock<Vec<String>>>,
     db: Arc<DatabaseConnection>,
     pub(crate) image_dir: PathBuf,
-    listen_port: u16,
-    frontend_url: Option<Url>,
+    image_cache: ImageCache,
+    not_found_pool: Arc<RwLock<Option<Vec<String>>>>,
+    /// Live status code metadata, admin edits swap entries in without a restart.
+    status_codes: Arc<RwLock<LiveStatusCodes>>,
+    /// Serialises edits to the overrides file so concurrent saves don't drop each other.
+    status_overrides_lock: Arc<Mutex<()>>,
+    /// Country lookups for vote origins, only when `--geoip-db` is set.
+    geoip: Option<Arc<GeoIp>>,
+    /// The all-time leaderboard and when it was built, summing every vote is too much per page view.
+    all_time_top_pets: Arc<RwLock<Option<CachedTopPets>>>,
+}
+
+/// A leaderboard and when it was built.
+type CachedTopPets = (Instant, Vec<TopPet>);
+
+/// Status code metadata and its name slugs, rebuilt together whenever an admin edits a status.
+#[derive(Debug)]
+pub(crate) struct LiveStatusCodes {
+    pub(crate) codes: StatusCodes,
+    pub(crate) slugs: StatusSlugs,
+}
+
+impl LiveStatusCodes {
+    fn new(codes: StatusCodes) -> Self {
+        let slugs = status_codes::status_slugs(&codes);
+        Self { codes, slugs }
+    }
 }
 
 impl AppState {
     fn new(
-        base_domain: &str,
-        frontend_url: Option<Url>,
+        config: AppConfig,
         enabled_pets: Vec<String>,
         db: Arc<DatabaseConnection>,
         image_dir: PathBuf,
-        listen_port: u16,
     ) -> Self {
-        let base_domain = base_domain
-            .trim()
-            .trim_end_matches(['.', '/'])
-            .to_ascii_lowercase();
-
         Self {
-            base_domain,
-            frontend_url,
+            config: Arc::new(config),
             enabled_pets: Arc::new(RwLock::new(enabled_pets)),
             db,
             image_dir,
-            listen_port,
+            image_cache: ImageCache::default(),
+            not_found_pool: Arc::new(RwLock::new(None)),
+            all_time_top_pets: Arc::new(RwLock::new(None)),
+            status_codes: Arc::new(RwLock::new(LiveStatusCodes::new(STATUS_CODES.clone()))),
+            status_overrides_lock: Arc::new(Mutex::new(())),
+            geoip: None,
         }
     }
 
+    /// Tests tweak single settings on an otherwise default config.
+    #[cfg(test)]
+    fn config_mut(&mut self) -> &mut AppConfig {
+        Arc::make_mut(&mut self.config)
+    }
+
+    /// The primary base domain.
+    pub(crate) fn base_domain(&self) -> &str {
+        self.config
+            .base_domains
+            .first()
+            .map(String::as_str)
+            .unwrap_or("localhost")
+    }
+
+    /// The configured base domain `host` belongs to, falling back to the primary.
+    pub(crate) fn base_domain_for_host(&self, host: &str) -> &str {
+        matching_base_domain(&self.config.base_domains, &normalize_host(host))
+            .unwrap_or_else(|| self.base_domain())
+    }
+
     pub fn base_url(&self) -> String {
-        if let Some(url) = self.frontend_url.as_ref() {
+        self.base_url_for(self.base_domain())
+    }
+
+    /// Gets the base URL for one of the configured base domains
+    pub(crate) fn base_url_for(&self, base_domain: &str) -> String {
+        if let Some(url) = self.config.frontend_url.as_ref() {
+            let mut url = url.clone();
+            // the frontend URL names the primary domain, other domains keep its scheme and path
+            if base_domain != self.base_domain()
+                && let Err(err) = url.set_host(Some(base_domain))
+            {
+                error!(error=?err, base_domain=%base_domain, "Failed to set host on URL {}", url);
+            }
             url.to_string().trim_end_matches('/').to_string()
-        } else if self.listen_port == 443 {
-            format!("https://{}", self.base_domain)
-        } else if self.listen_port == 80 {
-            format!("http://{}", self.base_domain)
+        } else if self.config.listen_port == 443 {
+            format!("https://{}", base_domain)
+        } else if self.config.listen_port == 80 {
+            format!("http://{}", base_domain)
         } else {
-            format!("http://{}:{}", self.base_domain, self.listen_port)
+            format!("http://{}:{}", base_domain, self.config.listen_port)
         }
     }
-    /// Gets the base URL for a given pet
-    pub fn pet_base_url(&self, pet: &str) -> String {
-        if let Some(url) = self.frontend_url.as_ref() {
+    /// The canonical public URL for non-HTML generators, preferring the override.
+    pub(crate) fn public_base_url(&self) -> String {
+        match self.config.public_base_url_override.as_ref() {
+            Some(url) => url.to_string().trim_end_matches('/').to_string(),
+            None => self.base_url(),
+        }
+    }
+
+    /// Gets the base URL for a given pet on one of the configured base domains
+    pub(crate) fn pet_base_url(&self, base_domain: &str, pet: &str) -> String {
+        if self.config.disable_subdomains {
+            return format!("{}/{}", self.base_url_for(base_domain), pet);
+        }
+        if let Some(url) = self.config.frontend_url.as_ref() {
             let mut pet_url = url.clone();
-            if let Err(err) = pet_url.set_host(Some(&format!("{}.{}", pet, self.base_domain))) {
+            if let Err(err) = pet_url.set_host(Some(&format!("{}.{}", pet, base_domain))) {
                 error!(error=?err, pet=%pet, "Failed to set pet host on URL {}", url);
             }
             pet_url.to_string().trim_end_matches('/').to_string()
-        } else if self.listen_port == 443 {
-            format!("https://{}.{}", pet, self.base_domain)
-        } else if self.listen_port == 80 {
-            format!("http://{}.{}", pet, self.base_domain)
+        } else if self.config.listen_port == 443 {
+            format!("https://{}.{}", pet, base_domain)
+        } else if self.config.listen_port == 80 {
+            format!("http://{}.{}", pet, base_domain)
         } else {
-            format!("http://{}.{}:{}", pet, self.base_domain, self.listen_port)
+            format!("http://{}.{}:{}", pet, base_domain, self.config.listen_port)
         }
     }
 
     /// Gets the image path for the given animal and status code
     pub fn image_path(&self, animal: &str, status_code: u16) -> std::path::PathBuf {
+        self.image_path_for_format(animal, status_code, ImageFormat::Jpeg)
+    }
+
+    /// Gets the path of a specific encoding of the image for an animal and status code
+    pub(crate) fn image_path_for_format(
+        &self,
+        animal: &str,
+        status_code: u16,
+        format: ImageFormat,
+    ) -> std::path::PathBuf {
         self.image_dir
             .join(animal)
-            .join(format!("{}.jpg", status_code))
+            .join(format!("{}.{}", status_code, format.extension()))
+    }
+
+    /// Removes the optional encodings stored next to a status image. Called whenever the
+    /// JPEG is replaced, so negotiation can't keep serving a WebP of the old picture.
+    pub(crate) async fn remove_image_variants(
+        &self,
+        animal: &str,
+        status_code: u16,
+    ) -> Result<(), HttpetError> {
+        for format in NEGOTIATED_IMAGE_FORMATS {
+            if format == ImageFormat::Jpeg {
+                continue;
+            }
+            match tokio::fs::remove_file(self.image_path_for_format(animal, status_code, format))
+                .await
+            {
+                Ok(()) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
     }
 
     pub(crate) async fn create_or_update_pet(
@@ -116,27 +241,13 @@ impl AppState {
         status: pets::PetStatus,
     ) -> Result<(), HttpetError> {
         let db_txn: DatabaseTransaction = self.db.as_ref().begin().await?;
-        let enabled = matches!(status, pets::PetStatus::Enabled);
-        match pets::Entity::find_by_name(&db_txn, pet_name).await? {
-            Some(model) => {
-                let mut am = model.into_active_model();
-                am.enabled = Set(enabled);
-                am.status = Set(status);
-                am.update(&db_txn).await?
-            }
-            None => {
-                pets::ActiveModel {
-                    name: Set(pet_name.to_string()),
-                    enabled: Set(enabled),
-                    status: Set(status),
-                    ..Default::default()
-                }
-                .insert(&db_txn)
-                .await?
-            }
-        };
-
+        pets::Entity::upsert_status(&db_txn, pet_name, status).await?;
         db_txn.commit().await?;
+        self.refresh_enabled_pets().await
+    }
+
+    /// Reloads the enabled pet list after pet statuses change.
+    pub(crate) async fn refresh_enabled_pets(&self) -> Result<(), HttpetError> {
         let mut enabled = self.enabled_pets.write().await;
         *enabled = pets::Entity::enabled(&self.db)
             .await?
@@ -162,6 +273,156 @@ impl AppState {
         *enabled = pets::Entity::enabled_names(&self.db).await?;
         Ok(())
     }
+
+    /// How a pet's site is named in page text, eg `dog.httpet.org` or `httpet.org/dog`.
+    pub(crate) fn pet_host(&self, base_domain: &str, pet: &str) -> String {
+        if self.config.disable_subdomains {
+            format!("{}/{}", base_domain, pet)
+        } else {
+            format!("{}.{}", pet, base_domain)
+        }
+    }
+
+    /// The voter's country, when a GeoIP database is configured and knows the address.
+    fn vote_country(&self, client_ip: Option<Extension<ClientIp>>) -> Option<String> {
+        let ip = client_ip?.0.0?;
+        self.geoip.as_ref()?.country(ip)
+    }
+
+    /// Stylesheet for the configured theme, loaded after the default styles.
+    pub(crate) fn theme_stylesheet(&self) -> Option<String> {
+        self.config
+            .theme
+            .as_ref()
+            .map(|theme| format!("/static/themes/{theme}.css"))
+    }
+
+    /// Drops cached images for a pet and rebuilds the 404 pool after its files change.
+    pub(crate) async fn invalidate_pet_images(&self, pet_name: &str) {
+        self.image_cache
+            .invalidate_dir(&self.image_dir.join(pet_name))
+            .await;
+        self.refresh_not_found_pool().await;
+    }
+
+    /// Drops every cached image and re-reads the enabled pets, for when images
+    /// have been synced to the volume outside the admin UI.
+    pub(crate) async fn reload_images(&self) -> Result<(), HttpetError> {
+        self.image_cache.clear().await;
+        self.refresh_not_found_pool().await;
+        let enabled = pets::Entity::enabled_names(&self.db).await?;
+        *self.enabled_pets.write().await = enabled;
+        Ok(())
+    }
+
+    /// Pets with a 404 image, scanning the image directory on first use.
+    pub(crate) async fn not_found_pool(&self) -> Vec<String> {
+        if let Some(pool) = self.not_found_pool.read().await.as_ref() {
+            return pool.clone();
+        }
+        self.refresh_not_found_pool().await
+    }
+
+    /// The ten `Voting` pets with the most votes ever, rebuilt once the cached copy is
+    /// older than [`ALL_TIME_TOP_PETS_CACHE_SECONDS`].
+    pub(crate) async fn all_time_top_pets(&self) -> Result<Vec<TopPet>, HttpetError> {
+        let max_age = std::time::Duration::from_secs(ALL_TIME_TOP_PETS_CACHE_SECONDS);
+        if let Some((built, top_pets)) = self.all_time_top_pets.read().await.as_ref()
+            && built.elapsed() < max_age
+        {
+            return Ok(top_pets.clone());
+        }
+
+        let vote_totals = votes::vote_totals(self.db.as_ref()).await?;
+        let mut top_pets: Vec<TopPet> = pets::Entity::voting(self.db.as_ref())
+            .await?
+            .into_iter()
+            .filter_map(|pet| {
+                let votes = vote_totals.get(&pet.id).copied().unwrap_or(0);
+                (votes > 0).then_some(TopPet {
+                    name: pet.name,
+                    votes,
+                    votable: pet.votable,
+                })
+            })
+            .collect();
+        top_pets.sort_by(|a, b| b.votes.cmp(&a.votes).then_with(|| a.name.cmp(&b.name)));
+        top_pets.truncate(10);
+        *self.all_time_top_pets.write().await = Some((Instant::now(), top_pets.clone()));
+        Ok(top_pets)
+    }
+
+    /// The error for a pet that isn't enabled. Pets that are voting or submitted go to
+    /// their vote page, unknown names get a 404 so typos don't each get a vote page.
+    pub(crate) async fn pet_not_enabled(&self, base_url: String, pet: &str) -> HttpetError {
+        match pets::Entity::find_by_name(self.db.as_ref(), pet).await {
+            Ok(Some(model))
+                if matches!(
+                    model.status,
+                    pets::PetStatus::Voting | pets::PetStatus::Submitted
+                ) =>
+            {
+                HttpetError::NeedsVote(base_url, pet.to_string())
+            }
+            Ok(_) => HttpetError::NotFound(format!("{}", json!({"animal": pet}))),
+            Err(err) => err.into(),
+        }
+    }
+
+    /// Metadata for a status code, including any overrides.
+    pub(crate) async fn status_info(&self, status_code: u16) -> Option<StatusInfo> {
+        self.status_codes
+            .read()
+            .await
+            .codes
+            .get(&status_code)
+            .cloned()
+    }
+
+    /// Persists a new name and summary for a status code to the overrides file,
+    /// then applies it to the live metadata.
+    pub(crate) async fn update_status_info(
+        &self,
+        status_code: u16,
+        name: String,
+        summary: String,
+    ) -> Result<(), HttpetError> {
+        let _editing = self.status_overrides_lock.lock().await;
+        if !self
+            .status_codes
+            .read()
+            .await
+            .codes
+            .contains_key(&status_code)
+        {
+            return Err(HttpetError::NotFound(status_code.to_string()));
+        }
+        // the file is read and written off the runtime, requests keep reading the old map meanwhile
+        let overrides_path = self.config.status_overrides_path.clone();
+        let overrides = tokio::task::spawn_blocking(move || {
+            let mut overrides = status_codes::load_overrides(&overrides_path)?;
+            let status_override = overrides.entry(status_code).or_default();
+            status_override.name = Some(name);
+            status_override.summary = Some(summary);
+            status_codes::save_overrides(&overrides_path, &overrides)?;
+            Ok::<_, HttpetError>(overrides)
+        })
+        .await
+        .map_err(|err| HttpetError::InternalServerError(err.to_string()))??;
+
+        let mut codes = self.status_codes.read().await.codes.clone();
+        status_codes::apply_overrides(&mut codes, overrides);
+        let updated = LiveStatusCodes::new(codes);
+        *self.status_codes.write().await = updated;
+        Ok(())
+    }
+
+    /// Rescans the image directory for pets with a 404 image.
+    pub(crate) async fn refresh_not_found_pool(&self) -> Vec<String> {
+        let pool = views::scan_not_found_pool(&self.image_dir).await;
+        *self.not_found_pool.write().await = Some(pool.clone());
+        pool
+    }
 }
 
 #[cfg(test)]
@@ -186,16 +447,14 @@ async fn get_status_handler(
     Path(status_code): Path<u16>,
 ) -> Result<axum::response::Response, HttpetError> {
     if let Some(animal) = domain.animal.as_deref() {
-        return pet_status_response(&state, animal, status_code, &headers).await;
+        return pet_status_response(&state, &domain.base_domain, animal, status_code, &headers)
+            .await;
     }
 
     // return a random animal image for the root domain
     let enabled = state.enabled_pets.read().await.clone();
     if enabled.is_empty() {
-        return Err(HttpetError::NotFound(format!(
-            "{}",
-            json!({"domain" : domain, "status_code": status_code})
-        )));
+        return Err(HttpetError::NoPetsEnabled);
     }
     let mut candidates = Vec::new();
     for animal in enabled {
@@ -227,11 +486,12 @@ async fn get_status_handler(
         }
     };
 
-    pet_status_response(&state, &animal, status_code, &headers).await
+    pet_status_response(&state, &domain.base_domain, &animal, status_code, &headers).await
 }
 
 async fn pet_status_response(
     state: &AppState,
+    base_domain: &str,
     animal: &str,
     status_code: u16,
     request_headers: &HeaderMap,
@@ -242,85 +502,101 @@ async fn pet_status_response(
         .await
         .contains(&animal.to_string());
     if !enabled {
-        return Err(HttpetError::NeedsVote(state.base_url(), animal.to_string()));
-    }
-    let image_path = state.image_path(animal, status_code);
-    let metadata = match tokio::fs::metadata(&image_path).await {
-        Ok(metadata) => metadata,
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-            return Err(HttpetError::NotFound(format!(
-                "{}",
-                json!({"animal": animal, "status_code": status_code})
-            )));
-        }
-        Err(err) => {
-            error!(
-                "Failed to read image metadata for {}: {}",
-                image_path.display(),
-                err
-            );
-            return Err(HttpetError::InternalServerError(err.to_string()));
+        return Err(state
+            .pet_not_enabled(state.base_url_for(base_domain), animal)
+            .await);
+    }
+    // no-cache clients get fresh headers from disk rather than the cached copy
+    let no_cache = request_is_no_cache(request_headers);
+    let load_image = |path: PathBuf| async move {
+        if no_cache {
+            state.image_cache.reload(&path).await
+        } else {
+            state.image_cache.get_or_load(&path).await
         }
     };
-    let cache_headers = ImageCacheHeaders::from_metadata(&metadata);
-    if is_not_modified(request_headers, &cache_headers) {
-        return not_modified_response(&cache_headers);
+    let mut format = negotiate_image_format(request_headers, &state.config.image_formats);
+    let mut image = load_image(state.image_path_for_format(animal, status_code, format)).await?;
+    if image.is_none() && format != ImageFormat::Jpeg {
+        format = ImageFormat::Jpeg;
+        image = load_image(state.image_path(animal, status_code)).await?;
     }
-    let mut builder = axum::response::Response::builder();
-    match tokio::fs::read(&image_path).await {
-        Ok(bytes) => {
+    let Some(image) = image else {
+        if state.config.placeholder_images
+            && status_codes_for(&state.image_dir, animal).await?.is_empty()
+        {
+            let mut response = placeholder_response()?;
             if let Ok(value) = HeaderValue::from_str(animal) {
-                builder = builder.header(X_HTTPET_ANIMAL, value);
+                response.headers_mut().insert(X_HTTPET_ANIMAL, value);
             }
-            builder = builder.header(CONTENT_TYPE, "image/jpeg");
-            builder = apply_cache_headers(builder, &cache_headers);
-            builder
-                .body(axum::body::Body::from(bytes))
-                .map_err(HttpetError::from)
-        }
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Err(HttpetError::NotFound(
-            format!("{}", json!({"animal": animal, "status_code": status_code})),
-        )),
-        Err(err) => {
-            error!(
-                "Failed to read image file {}: {}",
-                image_path.display(),
-                err
-            );
-            Err(HttpetError::InternalServerError(
-                "Failed to access image, contact an admin!".to_string(),
-            ))
+            return Ok(response);
         }
+        return Err(HttpetError::NotFound(format!(
+            "{}",
+            json!({"animal": animal, "status_code": status_code})
+        )));
+    };
+    let image_request = ImageRequest {
+        pet: animal.to_string(),
+        status_code,
+    };
+    let cache_headers = image.cache_headers(state.config.strong_etags);
+    if is_not_modified(request_headers, &cache_headers) {
+        let mut response = not_modified_response(&cache_headers)?;
+        add_vary(response.headers_mut(), &ACCEPT);
+        response.extensions_mut().insert(image_request);
+        return Ok(response);
+    }
+    let mut builder = axum::response::Response::builder();
+    if let Ok(value) = HeaderValue::from_str(animal) {
+        builder = builder.header(X_HTTPET_ANIMAL, value);
     }
+    builder = builder.header(CONTENT_TYPE, format.content_type());
+    builder = apply_cache_headers(builder, &cache_headers);
+    let mut response = builder
+        .extension(image_request)
+        .body(axum::body::Body::from(image.bytes))?;
+    add_vary(response.headers_mut(), &ACCEPT);
+    Ok(response)
 }
 
 async fn vote_pet_handler(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(name): Path<String>,
+    client_ip: Option<Extension<ClientIp>>,
     session: Session,
     Form(form): Form<VotePetForm>,
 ) -> Result<VoteThanksTemplate, HttpetError> {
     validate_csrf(&session, &form.csrf_token).await?;
     let name = normalize_pet_name_strict(&name)?;
-    record_vote(&state.db, &name).await?;
+    let country = state.vote_country(client_ip);
+    record_vote(&state.db, &name, country.as_deref()).await?;
     Ok(VoteThanksTemplate {
         name: name.clone(),
-        frontend_url: views::frontend_url_for_state(&state),
+        frontend_url: state.base_url_for(request_base_domain(&state, &headers)),
+        theme_stylesheet: state.theme_stylesheet(),
     })
 }
 
 /// View for voting page
 async fn vote_pet_view(
     State(state): State<AppState>,
+    headers: HeaderMap,
     session: Session,
     Path(name): Path<String>,
 ) -> Result<VotePageTemplate, HttpetError> {
     let csrf_token = csrf::csrf_token(&session).await?;
     let name = normalize_pet_name_strict(&name)?;
+    let voting_closed = pets::Entity::find_by_name(state.db.as_ref(), &name)
+        .await?
+        .is_some_and(|pet| !pet.votable);
     Ok(VotePageTemplate {
         name,
         csrf_token,
-        frontend_url: views::frontend_url_for_state(&state),
+        voting_closed,
+        frontend_url: state.base_url_for(request_base_domain(&state, &headers)),
+        theme_stylesheet: state.theme_stylesheet(),
     })
 }
 
@@ -337,15 +613,19 @@ struct VotePetForm {
 
 async fn vote_form_handler(
     State(state): State<AppState>,
+    headers: HeaderMap,
+    client_ip: Option<Extension<ClientIp>>,
     session: Session,
     Form(form): Form<VoteForm>,
 ) -> Result<VoteThanksTemplate, HttpetError> {
     validate_csrf(&session, &form.csrf_token).await?;
     let name = normalize_pet_name_strict(&form.name)?;
-    record_vote(&state.db, &name).await?;
+    let country = state.vote_country(client_ip);
+    record_vote(&state.db, &name, country.as_deref()).await?;
     Ok(VoteThanksTemplate {
         name,
-        frontend_url: views::frontend_url_for_state(&state),
+        frontend_url: state.base_url_for(request_base_domain(&state, &headers)),
+        theme_stylesheet: state.theme_stylesheet(),
     })
 }
 
@@ -356,34 +636,111 @@ async fn pet_or_status_handler(
     headers: HeaderMap,
     Path(segment): Path<String>,
 ) -> Result<axum::response::Response, HttpetError> {
-    if let Ok(status_code) = segment.parse::<u16>() {
+    if let Some(status_code) = parse_status_code(&segment) {
         return get_status_handler(domain, State(state), headers, Path(status_code)).await;
     }
 
     let pet = normalize_pet_name_strict(&segment)?;
-    views::pet_status_list(state, &pet).await
+    views::pet_status_list(state, &domain.base_domain, &pet).await
 }
 
 #[derive(Deserialize)]
 struct PetStatusPath {
     pet: String,
+    #[serde(deserialize_with = "deserialize_status_code")]
     status_code: u16,
 }
 
 async fn pet_status_handler(
+    domain: AnimalDomain,
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(path): Path<PetStatusPath>,
 ) -> Result<axum::response::Response, HttpetError> {
     let pet = normalize_pet_name_strict(&path.pet)?;
-    pet_status_response(&state, &pet, path.status_code, &headers).await
+    pet_status_response(
+        &state,
+        &domain.base_domain,
+        &pet,
+        path.status_code,
+        &headers,
+    )
+    .await
+}
+
+/// Every method and path registered in `create_router`, since axum can't list them.
+/// Keep this in sync when adding routes, `route_list_matches_router` sends each one through the router.
+const ROUTES: &[(&str, &str)] = &[
+    ("GET", "/healthz"),
+    ("GET", "/readyz"),
+    ("GET", "/__routes"),
+    ("GET", "/admin/"),
+    ("POST", "/admin/pets"),
+    ("POST", "/admin/onboard"),
+    ("GET", "/admin/pets/{name}"),
+    ("POST", "/admin/pets/{name}"),
+    ("GET", "/admin/pets/{name}/status/{status_code}"),
+    ("GET", "/admin/pets/{name}/images/{status_code}"),
+    ("GET", "/admin/pets/{name}/delete"),
+    ("POST", "/admin/pets/{name}/delete"),
+    ("POST", "/admin/images"),
+    ("POST", "/admin/images/copy"),
+    ("GET", "/admin/pending-upload"),
+    ("POST", "/admin/pending-upload/confirm"),
+    ("POST", "/admin/pending-upload/cancel"),
+    ("POST", "/admin/reload-images"),
+    ("GET", "/admin/status-codes/{status_code}"),
+    ("POST", "/admin/status-codes/{status_code}"),
+    ("GET", "/admin/{*wildcard}"),
+    ("POST", "/vote"),
+    ("OPTIONS", "/vote"),
+    ("GET", "/vote/{name}"),
+    ("POST", "/vote/{name}"),
+    ("OPTIONS", "/vote/{name}"),
+    ("GET", "/"),
+    ("GET", "/about"),
+    ("GET", "/sitemap.xml"),
+    ("GET", "/info/{pet}/{status_code}"),
+    ("GET", "/info/{status_code}"),
+    ("GET", "/preview/{pet}/{status_code}"),
+    ("GET", "/preview/{status_code}"),
+    ("GET", "/{status_code}/info"),
+    ("GET", "/{pet}/{status_code}"),
+    ("GET", "/{segment}/"),
+    ("GET", "/{segment}"),
+    ("GET", "/static"),
+];
+
+/// Lists the registered routes for configuring reverse proxies, only in debug mode.
+async fn routes_handler(
+    State(state): State<AppState>,
+) -> Result<axum::response::Response, HttpetError> {
+    if !state.config.debug {
+        return Err(HttpetError::NotFound("/__routes".to_string()));
+    }
+    let routes: Vec<_> = ROUTES
+        .iter()
+        .map(|(method, path)| json!({"method": method, "path": path}))
+        .collect();
+    Ok(axum::Json(routes).into_response())
 }
 
 fn create_router(state: &AppState) -> Result<Router<AppState>, HttpetError> {
     let static_service = ServeDir::new("./static").append_index_html_on_directories(false);
+    // multipart uploads stream the image to disk, so only they get past the default body limit
+    let upload_body_limit = DefaultBodyLimit::max(
+        usize::try_from(spooled_upload::upload_body_limit(
+            state.config.max_upload_bytes,
+        ))
+        .unwrap_or(usize::MAX),
+    );
     let admin_routes = Router::new()
         .route("/admin/", axum::routing::get(admin_handler))
         .route("/admin/pets", axum::routing::post(create_pet_handler))
+        .route(
+            "/admin/onboard",
+            axum::routing::post(onboard_pet_handler).layer(upload_body_limit),
+        )
         .route(
             "/admin/pets/{name}",
             axum::routing::get(admin_pet_view).post(update_pet_handler),
@@ -400,7 +757,34 @@ fn create_router(state: &AppState) -> Result<Router<AppState>, HttpetError> {
             "/admin/pets/{name}/delete",
             axum::routing::get(delete_pet_view).post(delete_pet_post),
         )
-        .route("/admin/images", axum::routing::post(upload_image_handler))
+        .route(
+            "/admin/images",
+            axum::routing::post(upload_image_handler).layer(upload_body_limit),
+        )
+        .route(
+            "/admin/images/copy",
+            axum::routing::post(copy_image_handler),
+        )
+        .route(
+            "/admin/pending-upload",
+            axum::routing::get(pending_upload_image),
+        )
+        .route(
+            "/admin/pending-upload/confirm",
+            axum::routing::post(confirm_pending_upload),
+        )
+        .route(
+            "/admin/pending-upload/cancel",
+            axum::routing::post(cancel_pending_upload),
+        )
+        .route(
+            "/admin/reload-images",
+            axum::routing::post(reload_images_handler),
+        )
+        .route(
+            "/admin/status-codes/{status_code}",
+            axum::routing::get(admin_status_code_view).post(update_status_code_handler),
+        )
         .route(
             "/admin/{*wildcard}",
             axum::routing::get(async move || Redirect::to("/admin/")),
@@ -408,19 +792,57 @@ fn create_router(state: &AppState) -> Result<Router<AppState>, HttpetError> {
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
             admin_base_domain_only,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            frame_options,
+        ));
+    let vote_routes = Router::new()
+        .route(
+            "/vote",
+            axum::routing::post(vote_form_handler).options(vote_preflight),
+        )
+        .route(
+            "/vote/{name}",
+            axum::routing::post(vote_pet_handler)
+                .get(vote_pet_view)
+                .options(vote_preflight),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            frame_options,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            vote_referer_check,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            vote_cors,
         ));
     let url = Url::from_str(&state.base_url())?;
 
-    let secure_cookies = state.listen_port == 443 || url.scheme() == "https";
+    let secure_cookies =
+        state.config.secure_cookies || state.config.listen_port == 443 || url.scheme() == "https";
     info!("Using secure cookies: {}", secure_cookies);
     let session_layer = SessionManagerLayer::new(MemoryStore::default())
         .with_expiry(Expiry::OnInactivity(Duration::seconds(CSRF_SESSION_LENGTH)))
         .with_secure(secure_cookies)
         .with_always_save(true);
-    Ok(Router::new()
+    let app_routes = Router::new()
         .merge(admin_routes)
-        .route("/", axum::routing::get(views::root_handler))
+        .merge(vote_routes)
+        .route("/__routes", axum::routing::get(routes_handler))
+        // the home page has one-click vote buttons, so it can't be framed either
+        .route(
+            "/",
+            axum::routing::get(views::root_handler).layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                frame_options,
+            )),
+        )
         .route("/about", axum::routing::get(views::about_view))
+        .route("/sitemap.xml", axum::routing::get(views::sitemap_handler))
         .route(
             "/info/{pet}/{status_code}",
             axum::routing::get(views::status_info_view),
@@ -441,11 +863,6 @@ fn create_router(state: &AppState) -> Result<Router<AppState>, HttpetError> {
             "/{status_code}/info",
             axum::routing::get(views::info_shortcut_handler),
         )
-        .route("/vote", axum::routing::post(vote_form_handler))
-        .route(
-            "/vote/{name}",
-            axum::routing::post(vote_pet_handler).get(vote_pet_view),
-        )
         .route(
             "/{pet}/{status_code}",
             axum::routing::get(pet_status_handler),
@@ -454,12 +871,23 @@ fn create_router(state: &AppState) -> Result<Router<AppState>, HttpetError> {
         .route("/{segment}", axum::routing::get(pet_or_status_handler))
         .nest_service("/static", axum::routing::get_service(static_service))
         .layer(session_layer)
-        .layer(DefaultBodyLimit::max(4096 * 1024 * 1024))
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
             not_found_template,
         ))
-        .layer(axum::middleware::from_fn(request_logger)))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            minify_html_response,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            request_logger,
+        ));
+    // probes are merged after the layers above so they skip sessions and request logs
+    Ok(Router::new()
+        .route("/healthz", axum::routing::get(probes::healthz))
+        .route("/readyz", axum::routing::get(probes::readyz))
+        .merge(app_routes))
 }
 
 pub(crate) fn normalize_pet_name(name: &str) -> String {
@@ -482,6 +910,31 @@ pub(crate) fn normalize_pet_name_strict(name: &str) -> Result<String, HttpetErro
     Ok(normalized)
 }
 
+/// Parses a status code path segment. Only ASCII digits are accepted, so `+200`
+/// is rejected while leading zeros are dropped (`0200` is `200`).
+pub(crate) fn parse_status_code(segment: &str) -> Option<u16> {
+    if segment.is_empty() || !segment.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+/// Serde helper applying [parse_status_code] to status code path parameters.
+pub(crate) fn deserialize_status_code<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let segment = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+    parse_status_code(&segment)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid status code: {segment}")))
+}
+
+/// A single status code path parameter.
+#[derive(Deserialize)]
+pub(crate) struct StatusCodeParam(
+    #[serde(deserialize_with = "deserialize_status_code")] pub(crate) u16,
+);
+
 async fn status_codes_for(image_dir: &StdPath, animal: &str) -> Result<Vec<u16>, HttpetError> {
     let dir = image_dir.join(animal);
     let mut entries = match tokio::fs::read_dir(&dir).await {
@@ -519,14 +972,42 @@ pub async fn setup_server(
     enabled_pets: Vec<String>,
     db: Arc<DatabaseConnection>,
 ) -> Result<(), HttpetError> {
-    let app_state = AppState::new(
-        cli.base_domain.as_str(),
-        cli.frontend_url.clone(),
+    let mut app_state = AppState::new(
+        AppConfig::from_cli(cli),
         enabled_pets,
         db,
         IMAGE_DIR.clone(),
-        cli.port.get(),
     );
+    if let Some(path) = cli.geoip_db.as_deref() {
+        app_state.geoip = Some(Arc::new(GeoIp::open(path)?));
+        info!("Loaded GeoIP database from {}", path);
+    }
+
+    let refresh_state = app_state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            NOT_FOUND_POOL_REFRESH_SECONDS,
+        ));
+        loop {
+            interval.tick().await;
+            refresh_state.refresh_not_found_pool().await;
+        }
+    });
+
+    // uploads orphaned by a restart go once they're past the max age, not at startup, since
+    // the temp dir is shared with any other instance on the host
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(PENDING_UPLOAD_SWEEP_SECONDS));
+        loop {
+            interval.tick().await;
+            pending_upload::sweep_stale(std::time::Duration::from_secs(
+                PENDING_UPLOAD_MAX_AGE_SECONDS,
+            ))
+            .await;
+        }
+    });
+
     let app = create_router(&app_state)?.with_state(app_state);
 
     let addr = format!("{}:{}", cli.listen_address, cli.port.get());
@@ -557,19 +1038,30 @@ mod tests {
     use axum::http::{
         Request,
         header::{
-            CACHE_CONTROL, CONTENT_TYPE, ETAG, EXPIRES, IF_NONE_MATCH, LAST_MODIFIED, PRAGMA,
-            SET_COOKIE,
+            ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+            ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL,
+            CONTENT_SECURITY_POLICY, CONTENT_TYPE, ETAG, EXPIRES, IF_NONE_MATCH, LAST_MODIFIED,
+            PRAGMA, SET_COOKIE, VARY, X_FRAME_OPTIONS,
         },
     };
+    use clap::Parser;
     use html_escape::decode_html_entities;
     use http_body_util::BodyExt;
-    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, Set};
     use sea_orm_migration::MigratorTrait;
     use tower::ServiceExt;
     use url::Url;
 
     pub(crate) async fn get_test_app() -> (AppState, Router) {
-        let state = setup_test_state().await;
+        get_test_app_with(|_| {}).await
+    }
+
+    /// Builds a test app after letting the caller adjust the state.
+    pub(crate) async fn get_test_app_with(
+        configure: impl FnOnce(&mut AppState),
+    ) -> (AppState, Router) {
+        let mut state = setup_test_state().await;
+        configure(&mut state);
 
         let app = create_router(&state)
             .expect("Failed to create router")
@@ -577,6 +1069,22 @@ mod tests {
         (state, app)
     }
 
+    /// Sends a bodyless request to `path` on the pet's subdomain.
+    async fn request_on_subdomain(
+        app: &Router,
+        pet: &str,
+        method: &str,
+        path: &str,
+    ) -> axum::response::Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header("host", &format!("{pet}.{}", TEST_BASE_DOMAIN))
+            .body(Body::empty())
+            .expect("create request");
+        app.clone().oneshot(request).await.expect("send request")
+    }
+
     async fn setup_test_state() -> AppState {
         let _ = setup_logging(true);
         let db = crate::db::connect_test_db().await.expect("connect test db");
@@ -591,12 +1099,10 @@ mod tests {
             .collect();
         let image_dir = tempfile::tempdir().expect("create temp image dir");
         AppState::new(
-            TEST_BASE_DOMAIN,
-            None,
+            AppConfig::new(&[TEST_BASE_DOMAIN], None, 0),
             enabled,
             db,
             image_dir.path().to_path_buf(),
-            0,
         )
     }
 
@@ -632,6 +1138,22 @@ mod tests {
         body[start..end].to_string()
     }
 
+    /// Opens an admin session, returning its CSRF token and session cookie.
+    async fn admin_csrf_session(app: &Router) -> (String, String) {
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        (
+            extract_csrf_token(&body),
+            cookie.expect("missing session cookie"),
+        )
+    }
+
     fn multipart_body(boundary: &str, parts: Vec<(&str, Vec<u8>, Option<&str>)>) -> Vec<u8> {
         let mut body = Vec::new();
         for (name, content, filename) in parts {
@@ -659,53 +1181,161 @@ mod tests {
         body
     }
 
+    #[tokio::test]
+    async fn hosts_are_normalized_before_matching_base_domains() {
+        let (state, app) = get_test_app_with(|state| {
+            state.config_mut().base_domains =
+                AppConfig::new(&[TEST_BASE_DOMAIN, "Bücher.Example.."], None, 3000).base_domains;
+        })
+        .await;
+        assert_eq!(
+            state.config.base_domains,
+            vec![TEST_BASE_DOMAIN, "xn--bcher-kva.example"]
+        );
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        // a host that doesn't match falls back to the apex page, so dog's list means it did
+        for (host, base_domain) in [
+            ("DOG.EXAMPLE.ORG", TEST_BASE_DOMAIN),
+            ("dog.example.org..", TEST_BASE_DOMAIN),
+            ("Dog.Example.Org.:3000", TEST_BASE_DOMAIN),
+            ("dog.bücher.example", "xn--bcher-kva.example"),
+            ("DOG.XN--BCHER-KVA.EXAMPLE.", "xn--bcher-kva.example"),
+        ] {
+            let request = Request::builder()
+                .uri("/")
+                .header("host", host)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK, "{host}");
+            let body = read_body(response).await;
+            assert!(
+                body.contains(&format!(
+                    "Status codes for {}",
+                    state.pet_host(base_domain, "dog")
+                )),
+                "{host} should list dog's codes"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn app_state_base_urls_trim_trailing_slashes() {
         let db = crate::db::connect_test_db().await.expect("connect test db");
         let image_dir = tempfile::tempdir().expect("create temp image dir");
 
         let app_state = AppState::new(
-            "example.com/",
-            None,
+            AppConfig::new(&["example.com/"], None, 3000),
             Vec::new(),
             db.clone(),
             image_dir.path().to_path_buf(),
-            3000,
         );
         assert_eq!(app_state.base_url(), "http://example.com:3000");
         assert!(!app_state.base_url().ends_with('/'));
-        assert_eq!(app_state.pet_base_url("dog"), "http://dog.example.com:3000");
-        assert!(!app_state.pet_base_url("dog").ends_with('/'));
+        assert_eq!(
+            app_state.pet_base_url("example.com", "dog"),
+            "http://dog.example.com:3000"
+        );
+        assert!(!app_state.pet_base_url("example.com", "dog").ends_with('/'));
 
         let frontend_url = Url::parse("https://example.com/front/").expect("parse frontend url");
         let app_state = AppState::new(
-            "example.com",
-            Some(frontend_url),
+            AppConfig::new(&["example.com"], Some(frontend_url), 443),
             Vec::new(),
             db,
             image_dir.path().to_path_buf(),
-            443,
         );
         assert_eq!(app_state.base_url(), "https://example.com/front");
         assert!(!app_state.base_url().ends_with('/'));
         assert_eq!(
-            app_state.pet_base_url("dog"),
+            app_state.pet_base_url("example.com", "dog"),
             "https://dog.example.com/front"
         );
-        assert!(!app_state.pet_base_url("dog").ends_with('/'));
+        assert!(!app_state.pet_base_url("example.com", "dog").ends_with('/'));
     }
 
     #[tokio::test]
-    async fn unenabled_pet_returns_vote_page() {
-        let (_state, app) = get_test_app().await;
+    async fn app_state_from_cli_config_derives_urls() {
+        let db = crate::db::connect_test_db().await.expect("connect test db");
+        let image_dir = tempfile::tempdir().expect("create temp image dir");
+        let config = AppConfig::from_cli(&CliOptions::parse_from([
+            "httpet",
+            "--base-domain",
+            "Example.ORG.,example.net",
+            "--port",
+            "8080",
+            "--public-base-url-override",
+            "https://cdn.example.com/pets/",
+            "--max-upload-bytes",
+            "1024",
+        ]));
+        assert_eq!(config.base_domains, vec!["example.org", "example.net"]);
+        assert_eq!(config.listen_port, 8080);
+        assert_eq!(config.max_upload_bytes, 1024);
+
+        let app_state = AppState::new(config, Vec::new(), db, image_dir.path().to_path_buf());
+        assert_eq!(app_state.base_domain(), "example.org");
+        assert_eq!(app_state.base_url(), "http://example.org:8080");
+        assert_eq!(
+            app_state.base_url_for("example.net"),
+            "http://example.net:8080"
+        );
+        assert_eq!(
+            app_state.pet_base_url("example.org", "dog"),
+            "http://dog.example.org:8080"
+        );
+        assert_eq!(
+            app_state.pet_base_url("example.net", "dog"),
+            "http://dog.example.net:8080"
+        );
+        assert_eq!(app_state.pet_host("example.net", "dog"), "dog.example.net");
+        assert_eq!(app_state.public_base_url(), "https://cdn.example.com/pets");
+    }
+
+    #[tokio::test]
+    async fn behind_proxy_preset_enables_proxy_settings() {
+        let db = crate::db::connect_test_db().await.expect("connect test db");
+        let image_dir = tempfile::tempdir().expect("create temp image dir");
+        let mut app_state = AppState::new(
+            AppConfig::new(&[TEST_BASE_DOMAIN], None, 3000),
+            Vec::new(),
+            db,
+            image_dir.path().to_path_buf(),
+        );
+
+        app_state
+            .config_mut()
+            .apply_cli_options(&CliOptions::parse_from(["httpet"]));
+        assert!(!app_state.config.trust_forwarded_proto);
+        assert!(!app_state.config.trust_forwarded_for);
+        assert!(!app_state.config.secure_cookies);
+
+        app_state
+            .config_mut()
+            .apply_cli_options(&CliOptions::parse_from(["httpet", "--behind-proxy"]));
+        assert!(app_state.config.trust_forwarded_proto);
+        assert!(app_state.config.trust_forwarded_for);
+        assert!(app_state.config.secure_cookies);
+    }
+
+    #[tokio::test]
+    async fn behind_proxy_admin_redirect_uses_forwarded_proto() {
+        let (_state, app) =
+            get_test_app_with(|state| state.config_mut().trust_forwarded_proto = true).await;
 
         let request = Request::builder()
             .method("GET")
-            .uri("/500")
+            .uri("/admin/")
             .header("host", &format!("dog.{}", TEST_BASE_DOMAIN))
+            .header("x-forwarded-proto", "https")
             .body(Body::empty())
             .expect("create request");
-        let response = app.clone().oneshot(request).await.expect("send request");
+        let response = app.oneshot(request).await.expect("send request");
 
         assert_eq!(response.status(), StatusCode::SEE_OTHER);
         let location = response
@@ -714,33 +1344,136 @@ mod tests {
             .expect("missing redirect location")
             .to_str()
             .expect("invalid location header");
-        assert!(location.contains("/vote/dog"));
+        assert!(location.starts_with(&format!("https://{}", TEST_BASE_DOMAIN)));
     }
 
     #[tokio::test]
-    async fn enabled_pet_sets_header_and_status() {
-        let (state, app) = get_test_app().await;
+    async fn image_formats_option_limits_negotiation() {
+        let (state, app) = get_test_app_with(|state| {
+            state
+                .config_mut()
+                .apply_cli_options(&CliOptions::parse_from([
+                    "httpet",
+                    "--image-formats",
+                    "jpeg",
+                ]));
+        })
+        .await;
+        assert_eq!(state.config.image_formats, vec![ImageFormat::Jpeg]);
         state
             .create_or_update_pet("dog", pets::PetStatus::Enabled)
             .await
             .expect("create pet");
-
         state.write_test_image("dog", 200);
+        std::fs::write(
+            state.image_path_for_format("dog", 200, ImageFormat::Webp),
+            b"RIFF",
+        )
+        .expect("write webp image");
 
         let request = Request::builder()
             .method("GET")
-            .uri("/200")
-            .header("host", &format!("dog.{}", TEST_BASE_DOMAIN))
+            .uri("/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("accept", "image/webp,*/*;q=0.8")
             .body(Body::empty())
             .expect("create request");
-        let response = app.clone().oneshot(request).await.expect("send request");
+        let response = app.oneshot(request).await.expect("send request");
 
+        assert_eq!(response.status(), StatusCode::OK);
         assert_eq!(
-            response.status(),
-            StatusCode::from_u16(200).expect("invalid status code")
+            response.headers().get(CONTENT_TYPE).expect("content type"),
+            "image/jpeg"
         );
-        assert_eq!(
-            response
+    }
+
+    #[tokio::test]
+    async fn unenabled_pet_returns_vote_page() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Submitted)
+            .await
+            .expect("create pet");
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/500").await;
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response
+            .headers()
+            .get("location")
+            .expect("missing redirect location")
+            .to_str()
+            .expect("invalid location header");
+        assert!(location.contains("/vote/dog"));
+    }
+
+    #[tokio::test]
+    async fn unknown_pet_returns_not_found_instead_of_vote_page() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        for (pet, path) in [
+            ("dgo", "/200"),
+            ("dgo", "/"),
+            ("www", "/dgo/200"),
+            ("www", "/dgo"),
+            ("www", "/info/dgo/200"),
+            ("www", "/preview/dgo/200"),
+        ] {
+            let response = request_on_subdomain(&app, pet, "GET", path).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{pet} {path}");
+            assert!(response.headers().get("location").is_none());
+        }
+        assert!(
+            pets::Entity::find_by_name(state.db.as_ref(), "dgo")
+                .await
+                .expect("fetch pet")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn voting_pet_subdomain_redirects_to_vote_page() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Voting)
+            .await
+            .expect("create pet");
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response
+            .headers()
+            .get("location")
+            .expect("missing redirect location")
+            .to_str()
+            .expect("invalid location header");
+        assert_eq!(location, format!("{}/vote/dog", state.base_url()));
+    }
+
+    #[tokio::test]
+    async fn enabled_pet_sets_header_and_status() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+
+        state.write_test_image("dog", 200);
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+
+        assert_eq!(
+            response.status(),
+            StatusCode::from_u16(200).expect("invalid status code")
+        );
+        assert_eq!(
+            response
                 .headers()
                 .get(X_HTTPET_ANIMAL)
                 .expect("missing header"),
@@ -757,6 +1490,85 @@ mod tests {
         assert!(!body.is_empty());
     }
 
+    #[tokio::test]
+    async fn info_status_name_slug_redirects_to_code() {
+        let (_state, app) = get_test_app().await;
+
+        for uri in ["/info/dog/not-found", "/info/dog/Not-Found"] {
+            let request = Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert!(response.status().is_redirection(), "{uri}");
+            assert_eq!(
+                response
+                    .headers()
+                    .get("location")
+                    .expect("missing location"),
+                "/info/dog/404"
+            );
+        }
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/info/dog/not-a-status")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn status_code_paths_only_accept_digits() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        for uri in ["/dog/0200", "/dog/200"] {
+            let request = Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+        }
+
+        for uri in ["/dog/+200", "/dog/-200", "/dog/2oo"] {
+            let request = Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/0200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = request_on_subdomain(&app, "dog", "GET", "/+200").await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn parse_status_code_rejects_signs_and_overflow() {
+        assert_eq!(parse_status_code("200"), Some(200));
+        assert_eq!(parse_status_code("0200"), Some(200));
+        assert_eq!(parse_status_code("+200"), None);
+        assert_eq!(parse_status_code(" 200"), None);
+        assert_eq!(parse_status_code(""), None);
+        assert_eq!(parse_status_code("70000"), None);
+    }
+
     #[tokio::test]
     async fn root_status_returns_enabled_pet_image() {
         let (state, app) = get_test_app().await;
@@ -837,6 +1649,86 @@ mod tests {
         assert_eq!(vote.vote_count, 2);
     }
 
+    #[tokio::test]
+    async fn votes_for_closed_pets_are_rejected() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("cat", pets::PetStatus::Voting)
+            .await
+            .expect("create pet");
+
+        let (admin_token, admin_cookie) = admin_csrf_session(&app).await;
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/pets/cat")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &admin_cookie)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(format!(
+                "status=voting&votable=false&csrf_token={admin_token}"
+            )))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let pet = pets::Entity::find_by_name(state.db.as_ref(), "cat")
+            .await
+            .expect("fetch pet")
+            .expect("pet exists");
+        assert_eq!(pet.status, pets::PetStatus::Voting);
+        assert!(!pet.votable);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/vote/cat")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = read_body(response).await;
+        assert!(body.contains("Voting is closed for cat"));
+        assert!(!body.contains("action=\"/vote/cat\""));
+
+        for (uri, form) in [
+            ("/vote/cat", format!("csrf_token={csrf_token}")),
+            ("/vote", format!("name=cat&csrf_token={csrf_token}")),
+        ] {
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("cookie", &cookie)
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(form))
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{uri}");
+            assert!(
+                read_body(response)
+                    .await
+                    .contains("Voting is closed for cat")
+            );
+        }
+
+        let vote_rows = votes::Entity::find()
+            .filter(votes::Column::PetId.eq(pet.id))
+            .all(state.db.as_ref())
+            .await
+            .expect("fetch votes");
+        assert!(vote_rows.is_empty());
+    }
+
     #[tokio::test]
     async fn vote_form_adds_pet() {
         let (state, app) = get_test_app().await;
@@ -879,6 +1771,68 @@ mod tests {
         assert_eq!(vote.vote_count, 1);
     }
 
+    #[tokio::test]
+    async fn vote_origins_are_tallied_by_geoip_country() {
+        let (state, app) = get_test_app_with(|state| {
+            state.config_mut().trust_forwarded_for = true;
+            state.geoip = Some(Arc::new(
+                GeoIp::from_bytes(geoip::tests::country_db(
+                    std::net::Ipv4Addr::new(81, 2, 69, 0),
+                    24,
+                    "GB",
+                ))
+                .expect("load fixture db"),
+            ));
+        })
+        .await;
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/vote/cat")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        // one vote from the fixture's GB network, one from an address it doesn't know
+        for client_ip in ["81.2.69.160", "192.0.2.1"] {
+            let request = Request::builder()
+                .method("POST")
+                .uri("/vote/cat")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .header("x-forwarded-for", client_ip)
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(format!("csrf_token={csrf_token}")))
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let totals = crate::db::entities::vote_countries::country_totals(
+            state.db.as_ref(),
+            Utc::now().date_naive(),
+        )
+        .await
+        .expect("country totals");
+        assert_eq!(totals, vec![("GB".to_string(), 1)]);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = read_body(response).await;
+        assert!(body.contains("Votes by country"));
+        assert!(body.contains(">GB</text>"));
+    }
+
     #[tokio::test]
     async fn vote_form_rejects_non_letters() {
         let (state, app) = get_test_app().await;
@@ -912,6 +1866,44 @@ mod tests {
         assert!(pet.is_none());
     }
 
+    #[tokio::test]
+    async fn apex_status_without_enabled_pets_explains_no_pets() {
+        let (_state, app) = get_test_app().await;
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(
+            response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.starts_with("text/html"))
+        );
+        let body = read_body(response).await;
+        assert!(body.contains("No pets yet"));
+        assert!(!body.contains("Page not found"));
+        assert!(!body.contains("status_code"));
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("accept", "application/json")
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: serde_json::Value =
+            serde_json::from_str(&read_body(response).await).expect("parse json");
+        assert_eq!(body["error"], "no_pets_enabled");
+    }
+
     #[tokio::test]
     async fn not_found_renders_template_with_image() {
         let (state, app) = get_test_app().await;
@@ -937,28 +1929,426 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn about_page_mentions_yaleman() {
-        let (_state, app) = get_test_app().await;
+    async fn not_found_reason_only_exposed_in_debug_mode() {
+        for debug in [false, true] {
+            let (state, app) = get_test_app_with(|state| state.config_mut().debug = debug).await;
+            state
+                .create_or_update_pet("dog", pets::PetStatus::Enabled)
+                .await
+                .expect("create pet");
 
-        let request = Request::builder()
-            .method("GET")
-            .uri("/about")
-            .header("host", TEST_BASE_DOMAIN)
-            .body(Body::empty())
-            .expect("create request");
-        let response = app.oneshot(request).await.expect("send request");
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = read_body(response).await;
+            let request = Request::builder()
+                .method("GET")
+                .uri("/dog/418")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("accept", "application/json")
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            assert_eq!(
+                response.headers().get(CONTENT_TYPE).expect("content type"),
+                "application/json"
+            );
+            assert!(response.headers().get(VARY).is_some());
+            let body: serde_json::Value =
+                serde_json::from_str(&read_body(response).await).expect("json body");
+            assert_eq!(body["error"], "not_found");
+            if debug {
+                assert_eq!(body["reason"]["animal"], "dog");
+                assert_eq!(body["reason"]["status_code"], 418);
+            } else {
+                assert!(body.get("reason").is_none());
+            }
 
-        assert!(body.contains("yaleman.org"));
-        assert!(body.contains("About - httpet"));
-        assert!(body.contains("/info/dog/404"));
+            let request = Request::builder()
+                .method("GET")
+                .uri("/dog/418")
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            let body = read_body(response).await;
+            assert!(body.contains("Not Found - httpet"));
+            assert_eq!(body.contains("not-found-reason"), debug);
+        }
     }
 
     #[tokio::test]
-    async fn admin_page_renders_pet_stats() {
+    async fn not_found_pool_updates_after_404_upload() {
         let (state, app) = get_test_app().await;
-
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/static/missing.css")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert!(!read_body(response).await.contains("/dog/404"));
+        assert_eq!(state.not_found_pool().await, Vec::<String>::new());
+
+        let boundary = "boundary404";
+        let jpeg_bytes =
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg")).to_vec();
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("pet", b"dog".to_vec(), None),
+                ("status_code", b"404".to_vec(), None),
+                ("csrf_token", csrf_token.into_bytes(), None),
+                ("image", jpeg_bytes, Some("dog.jpg")),
+            ],
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(state.not_found_pool().await, vec!["dog".to_string()]);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/static/missing.css")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert!(read_body(response).await.contains("/dog/404"));
+    }
+
+    #[tokio::test]
+    async fn theme_option_injects_stylesheet_link() {
+        let (state, app) = get_test_app_with(|state| {
+            state
+                .config_mut()
+                .apply_cli_options(&CliOptions::parse_from([
+                    "httpet",
+                    "--theme",
+                    "dark",
+                    "--no-minify-html",
+                ]));
+        })
+        .await;
+        assert_eq!(
+            state.theme_stylesheet().as_deref(),
+            Some("/static/themes/dark.css")
+        );
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        let body = read_body(response).await;
+        assert!(body.contains(r#"<link rel="stylesheet" href="/static/styles.css">"#));
+        assert!(body.contains(r#"<link rel="stylesheet" href="/static/themes/dark.css">"#));
+
+        let (state, _app) = get_test_app_with(|state| {
+            state
+                .config_mut()
+                .apply_cli_options(&CliOptions::parse_from(["httpet", "--theme", "../missing"]));
+        })
+        .await;
+        assert_eq!(state.theme_stylesheet(), None);
+    }
+
+    #[tokio::test]
+    async fn minified_about_page_is_smaller() {
+        let mut lengths = Vec::new();
+        for args in [vec!["httpet", "--no-minify-html"], vec!["httpet"]] {
+            let (state, app) = get_test_app_with(|state| {
+                state
+                    .config_mut()
+                    .apply_cli_options(&CliOptions::parse_from(args));
+            })
+            .await;
+            let request = Request::builder()
+                .method("GET")
+                .uri("/about")
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = read_body(response).await;
+            assert!(body.contains("yaleman"));
+            lengths.push((state.config.minify_html, body.len()));
+        }
+
+        assert_eq!(
+            lengths
+                .iter()
+                .map(|(minify, _)| *minify)
+                .collect::<Vec<_>>(),
+            vec![false, true]
+        );
+        assert!(lengths[1].1 < lengths[0].1, "{lengths:?}");
+    }
+
+    #[tokio::test]
+    async fn frame_options_only_on_admin_vote_and_home_pages() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        for uri in ["/admin/", "/vote/dog", "/"] {
+            let request = Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+            assert_eq!(
+                response
+                    .headers()
+                    .get(X_FRAME_OPTIONS)
+                    .expect("frame options"),
+                "DENY"
+            );
+            assert_eq!(
+                response
+                    .headers()
+                    .get(CONTENT_SECURITY_POLICY)
+                    .expect("csp"),
+                "frame-ancestors 'none'"
+            );
+        }
+
+        for uri in ["/dog/200", "/info/dog/200"] {
+            let request = Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+            assert!(response.headers().get(X_FRAME_OPTIONS).is_none(), "{uri}");
+            assert!(
+                response.headers().get(CONTENT_SECURITY_POLICY).is_none(),
+                "{uri}"
+            );
+        }
+
+        let (_state, app) = get_test_app_with(|state| {
+            state
+                .config_mut()
+                .apply_cli_options(&CliOptions::parse_from([
+                    "httpet",
+                    "--frame-options",
+                    "off",
+                ]));
+        })
+        .await;
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert!(response.headers().get(X_FRAME_OPTIONS).is_none());
+    }
+
+    #[tokio::test]
+    async fn check_referer_rejects_foreign_vote_origins() {
+        let (_state, app) =
+            get_test_app_with(|state| state.config_mut().check_referer = true).await;
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/vote/cat")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let vote = |header: Option<(&'static str, String)>| {
+            let app = app.clone();
+            let cookie = cookie.clone();
+            let csrf_token = csrf_token.clone();
+            async move {
+                let mut request = Request::builder()
+                    .method("POST")
+                    .uri("/vote/cat")
+                    .header("host", TEST_BASE_DOMAIN)
+                    .header("cookie", &cookie)
+                    .header(CONTENT_TYPE, "application/x-www-form-urlencoded");
+                if let Some((name, value)) = header {
+                    request = request.header(name, value);
+                }
+                let request = request
+                    .body(Body::from(format!("csrf_token={csrf_token}")))
+                    .expect("create request");
+                app.oneshot(request).await.expect("send request").status()
+            }
+        };
+
+        for accepted in [
+            ("origin", format!("http://{TEST_BASE_DOMAIN}")),
+            ("origin", format!("https://cat.{TEST_BASE_DOMAIN}")),
+            ("referer", format!("http://{TEST_BASE_DOMAIN}/vote/cat")),
+        ] {
+            assert_eq!(
+                vote(Some(accepted.clone())).await,
+                StatusCode::OK,
+                "{accepted:?}"
+            );
+        }
+        for rejected in [
+            ("origin", "https://evil.example.com".to_string()),
+            ("origin", format!("https://evil{TEST_BASE_DOMAIN}")),
+            ("origin", "null".to_string()),
+            ("referer", "https://evil.example.com/vote".to_string()),
+        ] {
+            assert_eq!(
+                vote(Some(rejected.clone())).await,
+                StatusCode::FORBIDDEN,
+                "{rejected:?}"
+            );
+        }
+        assert_eq!(vote(None).await, StatusCode::FORBIDDEN);
+
+        // the vote page itself is a GET and isn't checked
+        let request = Request::builder()
+            .method("GET")
+            .uri("/vote/cat")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("origin", "https://evil.example.com")
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn vote_preflight_returns_cors_allow_headers() {
+        let (_state, app) = get_test_app_with(|state| {
+            state.config_mut().cors_origins = vec!["https://friends.example.net".to_string()];
+        })
+        .await;
+
+        let preflight = |origin: &'static str| {
+            Request::builder()
+                .method("OPTIONS")
+                .uri("/vote")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("origin", origin)
+                .header("access-control-request-method", "POST")
+                .header("access-control-request-headers", "content-type")
+                .body(Body::empty())
+                .expect("create request")
+        };
+
+        let response = app
+            .clone()
+            .oneshot(preflight("https://friends.example.net"))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let headers = response.headers();
+        assert_eq!(
+            headers
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .expect("missing allow origin"),
+            "https://friends.example.net"
+        );
+        assert_eq!(
+            headers
+                .get(ACCESS_CONTROL_ALLOW_METHODS)
+                .expect("missing allow methods"),
+            "GET, POST, OPTIONS"
+        );
+        assert_eq!(
+            headers
+                .get(ACCESS_CONTROL_ALLOW_HEADERS)
+                .expect("missing allow headers"),
+            "Content-Type"
+        );
+        assert_eq!(
+            headers
+                .get(ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .expect("missing allow credentials"),
+            "true"
+        );
+
+        let response = app
+            .oneshot(preflight("https://elsewhere.example.net"))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(
+            response
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+        assert!(
+            response
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_METHODS)
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn about_page_mentions_yaleman() {
+        let (_state, app) = get_test_app().await;
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/about")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = read_body(response).await;
+
+        assert!(body.contains("yaleman.org"));
+        assert!(body.contains("About - httpet"));
+        assert!(body.contains("/info/dog/404"));
+    }
+
+    #[tokio::test]
+    async fn admin_page_renders_pet_stats() {
+        let (state, app) = get_test_app().await;
+
         let pet = pets::ActiveModel {
             name: Set("fox".to_string()),
             enabled: Set(true),
@@ -992,6 +2382,54 @@ mod tests {
         assert!(body.contains(&format!("fox.{}", TEST_BASE_DOMAIN)));
     }
 
+    #[tokio::test]
+    async fn admin_page_renders_pet_image_counts() {
+        let (state, app) = get_test_app().await;
+
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state
+            .create_or_update_pet("cat", pets::PetStatus::Voting)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+        std::fs::write(
+            state.image_dir.join("dog/404.jpg"),
+            [0xFF, 0xD8, 0xFF, 0xD9],
+        )
+        .expect("write extra image");
+        std::fs::write(state.image_dir.join("dog/notes.txt"), b"not an image")
+            .expect("write non-image file");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let body = read_body(response).await;
+
+        let dog_section = body
+            .split("href=\"/admin/pets/dog\"")
+            .nth(1)
+            .expect("missing dog section")
+            .split("</section>")
+            .next()
+            .expect("dog section body");
+        assert!(dog_section.contains("<strong>Images:</strong> 2"));
+        let cat_section = body
+            .split("href=\"/admin/pets/cat\"")
+            .nth(1)
+            .expect("missing cat section")
+            .split("</section>")
+            .next()
+            .expect("cat section body");
+        assert!(cat_section.contains("<strong>Images:</strong> 0"));
+    }
+
     #[tokio::test]
     async fn admin_page_prompts_for_orphan_image_folder() {
         let (state, app) = get_test_app().await;
@@ -1061,22 +2499,16 @@ mod tests {
             .expect("create request");
         let response = app.clone().oneshot(request).await.expect("send request");
         let body = read_body(response).await;
-        assert!(body.contains(&info.name));
-        assert!(body.contains(&info.summary));
-        assert!(body.contains(&info.mdn_url));
+        assert!(body.contains(&*info.name));
+        assert!(body.contains(&*info.summary));
+        assert!(body.contains(&*info.mdn_url));
     }
 
     #[tokio::test]
     async fn admin_redirects_non_base_domain() {
         let (_state, app) = get_test_app().await;
 
-        let request = Request::builder()
-            .method("GET")
-            .uri("/admin/?from=dog")
-            .header("host", &format!("dog.{}", TEST_BASE_DOMAIN))
-            .body(Body::empty())
-            .expect("create request");
-        let response = app.oneshot(request).await.expect("send request");
+        let response = request_on_subdomain(&app, "dog", "GET", "/admin/?from=dog").await;
 
         assert_eq!(response.status(), StatusCode::SEE_OTHER);
         let location = response
@@ -1090,20 +2522,499 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn homepage_lists_enabled_and_top_votes() {
-        let (state, app) = get_test_app().await;
+    async fn multiple_base_domains_resolve_pets_and_admin() {
+        let (state, app) = get_test_app_with(|state| {
+            state.config_mut().base_domains =
+                vec![TEST_BASE_DOMAIN.to_string(), "example.net".to_string()];
+        })
+        .await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
 
-        let dog = pets::ActiveModel {
-            name: Set("dog".to_string()),
-            enabled: Set(true),
-            status: Set(pets::PetStatus::Enabled),
-            ..Default::default()
+        for base_domain in [TEST_BASE_DOMAIN, "example.net"] {
+            let request = Request::builder()
+                .method("GET")
+                .uri("/200")
+                .header("host", format!("dog.{base_domain}"))
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response
+                    .headers()
+                    .get(X_HTTPET_ANIMAL)
+                    .expect("missing header"),
+                "dog"
+            );
+
+            let request = Request::builder()
+                .method("GET")
+                .uri("/admin/")
+                .header("host", base_domain)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let request = Request::builder()
+                .method("GET")
+                .uri("/admin/")
+                .header("host", format!("dog.{base_domain}"))
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+            let location = response
+                .headers()
+                .get("location")
+                .expect("missing redirect location")
+                .to_str()
+                .expect("invalid location header");
+            assert!(location.starts_with(&format!("http://{base_domain}:")));
+            assert!(location.ends_with("/admin/"));
         }
-        .insert(state.db.as_ref())
-        .await
-        .expect("insert dog");
-        let cat = pets::ActiveModel {
-            name: Set("cat".to_string()),
+    }
+
+    #[tokio::test]
+    async fn secondary_base_domain_pages_link_to_that_domain() {
+        let (state, app) = get_test_app_with(|state| {
+            state.config_mut().base_domains =
+                vec![TEST_BASE_DOMAIN.to_string(), "example.net".to_string()];
+        })
+        .await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        let get = |host: &'static str, uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let request = Request::builder()
+                    .method("GET")
+                    .uri(uri)
+                    .header("host", host)
+                    .body(Body::empty())
+                    .expect("create request");
+                read_body(app.oneshot(request).await.expect("send request")).await
+            }
+        };
+        let home_url = state.base_url_for("example.net");
+        let dog_url = state.pet_base_url("example.net", "dog");
+
+        let body = get("example.net", "/").await;
+        assert!(body.contains(&format!("href=\"{dog_url}\"")));
+        assert!(body.contains(&format!("href=\"{home_url}/about\"")));
+        assert!(!body.contains(&state.base_url()));
+
+        let body = get("example.net", "/about").await;
+        assert!(body.contains(&format!("{dog_url}/preview/404")));
+        assert!(!body.contains(&state.base_url()));
+
+        let body = get("example.net", "/info/dog/200").await;
+        assert!(body.contains(&format!("{home_url}/preview/dog/200")));
+        assert!(!body.contains(&state.base_url()));
+
+        let body = get("dog.example.net", "/").await;
+        assert!(body.contains("Status codes for dog.example.net"));
+
+        // dog has no 404 image, so this renders the not found page
+        let body = get("example.net", "/info/dog/404").await;
+        assert!(body.contains(&format!("href=\"{home_url}\"")));
+        assert!(!body.contains(&state.base_url()));
+    }
+
+    #[tokio::test]
+    async fn disabled_subdomains_treat_pet_hosts_as_apex() {
+        let (state, app) =
+            get_test_app_with(|state| state.config_mut().disable_subdomains = true).await;
+        for pet in ["dog", "cat"] {
+            state
+                .create_or_update_pet(pet, pets::PetStatus::Enabled)
+                .await
+                .expect("create pet");
+        }
+        state.write_test_image("cat", 200);
+
+        // the apex picks from pets with the image, so the dog host can only get the cat
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(X_HTTPET_ANIMAL)
+                .expect("missing header"),
+            "cat"
+        );
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = read_body(response).await;
+        assert!(body.contains(&format!("href=\"{}/dog\"", state.base_url())));
+        assert!(!body.contains(&format!("dog.{TEST_BASE_DOMAIN}")));
+
+        let response = request_on_subdomain(&app, "cat", "GET", "/cat/").await;
+        let body = read_body(response).await;
+        assert!(body.contains(&format!("Status codes for {TEST_BASE_DOMAIN}/cat")));
+        assert!(body.contains("href=\"/info/cat/200\""));
+    }
+
+    #[test]
+    fn matching_base_domain_prefers_most_specific() {
+        let base_domains = vec!["example.com".to_string(), "pets.example.com".to_string()];
+        assert_eq!(
+            matching_base_domain(&base_domains, "dog.pets.example.com"),
+            Some("pets.example.com")
+        );
+        assert_eq!(
+            matching_base_domain(&base_domains, "dog.example.com"),
+            Some("example.com")
+        );
+        assert_eq!(
+            matching_base_domain(&base_domains, "example.com"),
+            Some("example.com")
+        );
+        assert_eq!(matching_base_domain(&base_domains, "badexample.com"), None);
+    }
+
+    /// The paths `create_router` registers, read from this file's source since axum can't list them.
+    fn paths_in_create_router() -> std::collections::BTreeSet<String> {
+        let source = include_str!("mod.rs");
+        let start = source
+            .find("fn create_router(")
+            .expect("find create_router");
+        let end = start
+            + source[start..]
+                .find("\n}\n")
+                .expect("find end of create_router");
+        let path_re =
+            regex::Regex::new(r#"\.(?:route|nest_service)\(\s*"([^"]+)""#).expect("path regex");
+        path_re
+            .captures_iter(&source[start..end])
+            .map(|captures| captures[1].to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn route_list_matches_router() {
+        let listed: std::collections::BTreeSet<_> = ROUTES.iter().collect();
+        assert_eq!(listed.len(), ROUTES.len(), "duplicate entries in ROUTES");
+        // every registered path is listed...
+        let listed_paths: std::collections::BTreeSet<String> =
+            ROUTES.iter().map(|(_, path)| path.to_string()).collect();
+        assert_eq!(listed_paths, paths_in_create_router());
+
+        let mut state = setup_test_state().await;
+        state.config_mut().debug = true;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+        // a teapot marks requests no route picked up, so they can't pass as a handler's 404
+        let app = create_router(&state)
+            .expect("Failed to create router")
+            .fallback(async || StatusCode::IM_A_TEAPOT)
+            .with_state(state);
+
+        for (method, path) in ROUTES {
+            let uri = path
+                .replace("{name}", "dog")
+                .replace("{pet}", "dog")
+                .replace("{segment}", "dog")
+                .replace("{status_code}", "200")
+                .replace("{*wildcard}", "anything");
+            let request = Request::builder()
+                .method(*method)
+                .uri(&uri)
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            // handlers may still answer 404 (no pending upload, a bare `/static`), that's routed
+            assert!(
+                ![StatusCode::METHOD_NOT_ALLOWED, StatusCode::IM_A_TEAPOT]
+                    .contains(&response.status()),
+                "{method} {uri} isn't routed, got {}",
+                response.status()
+            );
+
+            // ...and so is every method it answers
+            for other in ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"] {
+                if listed.contains(&(other, *path)) {
+                    continue;
+                }
+                let request = Request::builder()
+                    .method(other)
+                    .uri(&uri)
+                    .header("host", TEST_BASE_DOMAIN)
+                    .body(Body::empty())
+                    .expect("create request");
+                let response = app.clone().oneshot(request).await.expect("send request");
+                assert_eq!(
+                    response.status(),
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "{other} {path} is routed but not in ROUTES"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_endpoint_only_in_debug_mode() {
+        let (_state, app) = get_test_app().await;
+        let request = Request::builder()
+            .method("GET")
+            .uri("/__routes")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let (_state, app) = get_test_app_with(|state| state.config_mut().debug = true).await;
+        let request = Request::builder()
+            .method("GET")
+            .uri("/__routes")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value =
+            serde_json::from_str(&read_body(response).await).expect("parse routes json");
+        let routes = body.as_array().expect("routes array");
+        assert_eq!(routes.len(), ROUTES.len());
+        assert!(routes.contains(&json!({"method": "POST", "path": "/admin/images"})));
+    }
+
+    #[tokio::test]
+    async fn status_list_includes_codes_without_metadata() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+        std::fs::write(state.image_path("dog", 299), [0xFF, 0xD8, 0xFF, 0xD9])
+            .expect("write custom code image");
+        assert!(!STATUS_CODES.contains_key(&299));
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = read_body(response).await;
+        assert!(body.contains("<strong>200</strong>"));
+        assert!(body.contains("<strong>299</strong>"));
+        assert!(body.contains("Unknown Status Code"));
+    }
+
+    #[tokio::test]
+    async fn image_responses_carry_pet_and_code_for_logging() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let image = response
+            .extensions()
+            .get::<ImageRequest>()
+            .expect("missing image request extension");
+        assert_eq!(image.pet, "dog");
+        assert_eq!(image.status_code, 200);
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/").await;
+        assert!(response.extensions().get::<ImageRequest>().is_none());
+    }
+
+    #[tokio::test]
+    async fn leaderboard_excludes_pets_below_min_votes() {
+        let (state, app) =
+            get_test_app_with(|state| state.config_mut().leaderboard_min_votes = 5).await;
+
+        let today = Utc::now().date_naive();
+        for (name, vote_count) in [("cat", 6), ("owl", 4)] {
+            let pet = pets::ActiveModel {
+                name: Set(name.to_string()),
+                enabled: Set(false),
+                status: Set(pets::PetStatus::Voting),
+                ..Default::default()
+            }
+            .insert(state.db.as_ref())
+            .await
+            .expect("insert pet");
+            votes::ActiveModel {
+                pet_id: Set(pet.id),
+                vote_date: Set(today),
+                vote_count: Set(vote_count),
+                ..Default::default()
+            }
+            .insert(state.db.as_ref())
+            .await
+            .expect("insert votes");
+        }
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        let body = read_body(response).await;
+        let top_votes_section = body
+            .split("Top votes (last 7 days)")
+            .nth(1)
+            .expect("missing top votes section")
+            .split("</section>")
+            .next()
+            .expect("missing top votes section body");
+        assert!(top_votes_section.contains("cat"));
+        assert!(!top_votes_section.contains("owl"));
+    }
+
+    #[tokio::test]
+    async fn leaderboard_hides_vote_button_for_closed_pets() {
+        let (state, app) = get_test_app().await;
+
+        let today = Utc::now().date_naive();
+        for (name, votable) in [("cat", true), ("owl", false)] {
+            let pet = pets::ActiveModel {
+                name: Set(name.to_string()),
+                status: Set(pets::PetStatus::Voting),
+                votable: Set(votable),
+                ..Default::default()
+            }
+            .insert(state.db.as_ref())
+            .await
+            .expect("insert pet");
+            votes::ActiveModel {
+                pet_id: Set(pet.id),
+                vote_date: Set(today),
+                vote_count: Set(3),
+                ..Default::default()
+            }
+            .insert(state.db.as_ref())
+            .await
+            .expect("insert votes");
+        }
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        let body = read_body(response).await;
+        let top_votes_section = body
+            .split("Top votes (last 7 days)")
+            .nth(1)
+            .expect("missing top votes section")
+            .split("</section>")
+            .next()
+            .expect("missing top votes section body");
+        assert!(top_votes_section.contains("action=\"/vote/cat\""));
+        assert!(top_votes_section.contains("owl"));
+        assert!(!top_votes_section.contains("action=\"/vote/owl\""));
+        assert!(top_votes_section.contains("voting closed"));
+    }
+
+    #[tokio::test]
+    async fn all_time_top_pets_include_votes_outside_the_week() {
+        let (state, app) = get_test_app().await;
+
+        let today = Utc::now().date_naive();
+        for (name, status, days_ago, vote_count) in [
+            ("cat", pets::PetStatus::Voting, 0, 3),
+            ("owl", pets::PetStatus::Voting, 30, 5),
+            ("dog", pets::PetStatus::Enabled, 40, 9),
+        ] {
+            let pet = pets::ActiveModel {
+                name: Set(name.to_string()),
+                status: Set(status),
+                ..Default::default()
+            }
+            .insert(state.db.as_ref())
+            .await
+            .expect("insert pet");
+            votes::ActiveModel {
+                pet_id: Set(pet.id),
+                vote_date: Set(today - chrono::Duration::days(days_ago)),
+                vote_count: Set(vote_count),
+                ..Default::default()
+            }
+            .insert(state.db.as_ref())
+            .await
+            .expect("insert votes");
+        }
+
+        let home_sections = || async {
+            let request = Request::builder()
+                .method("GET")
+                .uri("/")
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let body = read_body(app.clone().oneshot(request).await.expect("send request")).await;
+            let section = |heading: &str| {
+                body.split(heading)
+                    .nth(1)
+                    .unwrap_or_else(|| panic!("missing {heading} section"))
+                    .split("</section>")
+                    .next()
+                    .expect("section body")
+                    .to_string()
+            };
+            (
+                section("Top votes (last 7 days)"),
+                section("Top votes (all time)"),
+            )
+        };
+
+        let (weekly, all_time) = home_sections().await;
+        assert!(weekly.contains("cat"));
+        assert!(!weekly.contains("owl"));
+        let owl = all_time.find("owl").expect("owl listed all time");
+        let cat = all_time.find("cat").expect("cat listed all time");
+        assert!(owl < cat, "most votes first");
+        assert!(all_time.contains("(5 votes)"));
+        assert!(!all_time.contains("dog"), "only voting pets are ranked");
+
+        // the list is cached for a while, so a new vote doesn't show straight away
+        record_vote(&state.db, "yak", None)
+            .await
+            .expect("record vote");
+        pets::Entity::upsert_status(state.db.as_ref(), "yak", pets::PetStatus::Voting)
+            .await
+            .expect("start voting");
+        let (_weekly, all_time) = home_sections().await;
+        assert!(!all_time.contains("yak"));
+    }
+
+    #[tokio::test]
+    async fn homepage_lists_enabled_and_top_votes() {
+        let (state, app) = get_test_app().await;
+
+        let dog = pets::ActiveModel {
+            name: Set("dog".to_string()),
+            enabled: Set(true),
+            status: Set(pets::PetStatus::Enabled),
+            ..Default::default()
+        }
+        .insert(state.db.as_ref())
+        .await
+        .expect("insert dog");
+        let cat = pets::ActiveModel {
+            name: Set("cat".to_string()),
             enabled: Set(false),
             status: Set(pets::PetStatus::Voting),
             ..Default::default()
@@ -1187,17 +3098,11 @@ mod tests {
         state.write_test_image("dog", 404);
         let info = STATUS_CODES.get(&404).expect("status info");
 
-        let request = Request::builder()
-            .method("GET")
-            .uri("/")
-            .header("host", &format!("dog.{}", TEST_BASE_DOMAIN))
-            .body(Body::empty())
-            .expect("create request");
-        let response = app.oneshot(request).await.expect("send request");
+        let response = request_on_subdomain(&app, "dog", "GET", "/").await;
         let body = read_body(response).await;
         assert!(body.contains("httpet"));
         assert!(body.contains("404"));
-        assert!(body.contains(&info.name));
+        assert!(body.contains(&*info.name));
         assert!(body.contains("href=\"/info/404\""));
         assert!(!body.contains("href=\"/404\""));
     }
@@ -1224,7 +3129,7 @@ mod tests {
         assert!(body.contains("httpet"));
         assert!(body.contains("[MDN]"));
         assert!(body.contains("404"));
-        assert!(body.contains(&info.name));
+        assert!(body.contains(&*info.name));
         assert!(body.contains("href=\"/info/dog/404\""));
         assert!(!body.contains("href=\"/dog/404\""));
     }
@@ -1283,6 +3188,76 @@ mod tests {
         assert!(!body.is_empty());
     }
 
+    #[tokio::test]
+    async fn image_responses_expire_in_the_future() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let expires = response
+            .headers()
+            .get(EXPIRES)
+            .expect("missing expires header")
+            .to_str()
+            .expect("invalid expires header");
+        let expires = httpdate::parse_http_date(expires).expect("parse expires header");
+        assert!(expires > std::time::SystemTime::now());
+    }
+
+    #[tokio::test]
+    async fn enabled_pet_without_images_serves_placeholder() {
+        let (state, app) =
+            get_test_app_with(|state| state.config_mut().placeholder_images = true).await;
+        for pet in ["dog", "cat"] {
+            state
+                .create_or_update_pet(pet, pets::PetStatus::Enabled)
+                .await
+                .expect("create pet");
+        }
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_TYPE)
+                .expect("missing header"),
+            "image/svg+xml"
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get(CACHE_CONTROL)
+                .expect("missing header"),
+            "no-store"
+        );
+        assert!(read_body(response).await.contains("coming soon"));
+
+        // pets with some images still 404 for the codes they're missing
+        state.write_test_image("cat", 200);
+        let response = request_on_subdomain(&app, "cat", "GET", "/201").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn path_status_returns_not_modified_with_etag() {
         let (state, app) = get_test_app().await;
@@ -1328,13 +3303,149 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn admin_image_includes_cache_headers() {
-        let (state, app) = get_test_app().await;
+    async fn path_status_uses_strong_etag_when_enabled() {
+        let (state, app) = get_test_app_with(|state| state.config_mut().strong_etags = true).await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
         state.write_test_image("dog", 200);
 
         let request = Request::builder()
             .method("GET")
-            .uri("/admin/pets/dog/images/200")
+            .uri("/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .expect("missing etag header")
+            .to_str()
+            .expect("etag header value")
+            .to_string();
+        assert!(!etag.starts_with("W/"));
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .header(IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn no_cache_request_skips_not_modified() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .expect("missing etag header")
+            .clone();
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .header(IF_NONE_MATCH, etag.clone())
+            .header(CACHE_CONTROL, "no-cache")
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(ETAG), Some(&etag));
+        assert!(!read_body(response).await.is_empty());
+        assert_eq!(state.image_cache.loads(), 2);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .header(IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn path_status_negotiates_webp_variant_with_vary() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+        std::fs::write(
+            state.image_path_for_format("dog", 200, ImageFormat::Webp),
+            b"RIFF\x00\x00\x00\x00WEBP",
+        )
+        .expect("write webp variant");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .header(ACCEPT, "image/avif,image/webp,*/*;q=0.8")
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_TYPE)
+                .expect("missing content-type"),
+            "image/webp"
+        );
+        assert_eq!(
+            response.headers().get(VARY).expect("missing vary"),
+            "accept"
+        );
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .header(ACCEPT, "*/*")
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_TYPE)
+                .expect("missing content-type"),
+            "image/jpeg"
+        );
+        assert_eq!(
+            response.headers().get(VARY).expect("missing vary"),
+            "accept"
+        );
+    }
+
+    #[tokio::test]
+    async fn admin_image_includes_cache_headers() {
+        let (state, app) = get_test_app().await;
+        state.write_test_image("dog", 200);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/pets/dog/images/200")
             .header("host", TEST_BASE_DOMAIN)
             .body(Body::empty())
             .expect("create request");
@@ -1378,12 +3489,191 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
 
         let body = read_body(response).await;
-        assert!(body.contains(&info.name));
+        assert!(body.contains(&*info.name));
         let decoded_body = decode_html_entities(&body);
-        assert!(decoded_body.contains(&info.summary));
+        assert!(decoded_body.contains(&*info.summary));
         assert!(body.contains("/dog/200"));
     }
 
+    #[tokio::test]
+    async fn status_code_entries_share_text_with_live_metadata() {
+        let state = setup_test_state().await;
+        let live = state.status_codes.read().await;
+        let status_info = &live.codes;
+        // every possible code, plus a custom one without metadata
+        let codes: Vec<u16> = (100..=599).chain([799]).collect();
+
+        // build the list repeatedly like busy list pages would, each pass must agree
+        for _ in 0..50 {
+            let entries = views::status_code_entries(status_info, &codes);
+            assert_eq!(entries.len(), codes.len());
+            for (entry, code) in entries.iter().zip(&codes) {
+                assert_eq!(entry.code, *code);
+                match status_info.get(code) {
+                    Some(info) => {
+                        assert!(Arc::ptr_eq(&entry.name, &info.name));
+                        assert!(Arc::ptr_eq(&entry.summary, &info.summary));
+                        assert!(Arc::ptr_eq(&entry.mdn_url, &info.mdn_url));
+                    }
+                    None => {
+                        assert_eq!(&*entry.name, "Unknown Status Code");
+                        assert_eq!(&*entry.mdn_url, crate::status_codes::MDN_STATUS_URL);
+                    }
+                }
+            }
+        }
+
+        let entries = views::status_code_entries(status_info, &[404, 799]);
+        assert_eq!(&*entries[0].name, "Not Found");
+        assert_eq!(&*entries[1].name, "Unknown Status Code");
+    }
+
+    #[tokio::test]
+    async fn admin_status_code_edit_changes_info_page() {
+        let overrides_dir = tempfile::tempdir().expect("create overrides dir");
+        let overrides_path = overrides_dir.path().join("status_codes.overrides.json");
+        let (state, app) = get_test_app_with(|state| {
+            state.config_mut().status_overrides_path = overrides_path.clone();
+        })
+        .await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 404);
+        let info = STATUS_CODES.get(&404).expect("status info");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/status-codes/404")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/status-codes/404")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::from("csrf_token=invalid&name=Not+Found&summary=Nope"))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/status-codes/404")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::from(format!(
+                "csrf_token={csrf_token}&name=Not+Found&summary=Nobody+here+but+us+pets."
+            )))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/info/dog/404")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = read_body(response).await;
+        assert!(body.contains("Nobody here but us pets."));
+        assert!(!decode_html_entities(&body).contains(&*info.summary));
+
+        let overrides =
+            crate::status_codes::load_overrides(&overrides_path).expect("load overrides");
+        assert_eq!(
+            overrides.get(&404).and_then(|o| o.summary.as_deref()),
+            Some("Nobody here but us pets.")
+        );
+        // the shared startup metadata is left alone
+        assert_eq!(STATUS_CODES.get(&404), Some(info));
+    }
+
+    #[tokio::test]
+    async fn renamed_status_updates_name_slugs() {
+        let overrides_dir = tempfile::tempdir().expect("create overrides dir");
+        let overrides_path = overrides_dir.path().join("status_codes.overrides.json");
+        let (state, app) = get_test_app_with(|state| {
+            state.config_mut().status_overrides_path = overrides_path.clone();
+        })
+        .await;
+        state
+            .update_status_info(404, "Lost Pet".to_string(), "Gone walkabout.".to_string())
+            .await
+            .expect("update status");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/info/dog/lost-pet")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            response.headers().get("location").expect("location header"),
+            "/info/dog/404"
+        );
+
+        // the old name's slug is gone with it
+        let request = Request::builder()
+            .method("GET")
+            .uri("/info/dog/not-found")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn sitemap_uses_public_base_url_override() {
+        let (state, app) = get_test_app_with(|state| {
+            state.config_mut().frontend_url =
+                Some(Url::parse("https://example.org").expect("parse url"));
+            state.config_mut().public_base_url_override =
+                Some(Url::parse("https://cdn.example.net/").expect("parse url"));
+        })
+        .await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/sitemap.xml")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_TYPE)
+                .expect("missing content type"),
+            "application/xml; charset=utf-8"
+        );
+        let body = read_body(response).await;
+        assert!(body.contains("<loc>https://cdn.example.net/</loc>"));
+        assert!(body.contains("<loc>https://cdn.example.net/dog</loc>"));
+        assert!(body.contains("<loc>https://cdn.example.net/info/dog/200</loc>"));
+        assert!(!body.contains("https://example.org"));
+    }
+
     #[tokio::test]
     async fn status_info_shortcut_redirects_home() {
         let (_state, app) = get_test_app().await;
@@ -1435,22 +3725,95 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn preview_image_subdomain_returns_svg() {
+    async fn preview_image_keeps_non_square_aspect() {
         let (state, app) = get_test_app().await;
         state
             .create_or_update_pet("dog", pets::PetStatus::Enabled)
             .await
             .expect("create pet");
-        state.write_test_image("dog", 200);
+        let image_path = state.write_test_image("dog", 200);
+        image::DynamicImage::new_rgb8(300, 150)
+            .save_with_format(&image_path, image::ImageFormat::Jpeg)
+            .expect("write landscape image");
 
         let request = Request::builder()
             .method("GET")
-            .uri("/preview/200")
-            .header("host", &format!("dog.{}", TEST_BASE_DOMAIN))
+            .uri("/preview/dog/200")
+            .header("host", TEST_BASE_DOMAIN)
             .body(Body::empty())
             .expect("create request");
         let response = app.oneshot(request).await.expect("send request");
         assert_eq!(response.status(), StatusCode::OK);
+        let body = read_body(response).await;
+        let image_tag = body
+            .split("<image ")
+            .nth(1)
+            .and_then(|rest| rest.split("/>").next())
+            .expect("missing image element");
+        let attribute = |name: &str| -> u32 {
+            image_tag
+                .split(&format!(" {name}=\""))
+                .nth(1)
+                .and_then(|rest| rest.split('"').next())
+                .and_then(|value| value.parse().ok())
+                .expect("missing numeric attribute")
+        };
+        assert_eq!((attribute("width"), attribute("height")), (420, 210));
+        assert_eq!(attribute("width") * 150, attribute("height") * 300);
+    }
+
+    #[tokio::test]
+    async fn preview_image_dark_scheme_changes_background() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        let background = |body: &str| -> String {
+            body.split("<rect width=\"100%\" height=\"100%\" fill=\"")
+                .nth(1)
+                .and_then(|rest| rest.split('"').next())
+                .expect("missing background rect")
+                .to_string()
+        };
+        let preview = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let request = Request::builder()
+                    .method("GET")
+                    .uri(uri)
+                    .header("host", TEST_BASE_DOMAIN)
+                    .body(Body::empty())
+                    .expect("create request");
+                let response = app.oneshot(request).await.expect("send request");
+                assert_eq!(response.status(), StatusCode::OK);
+                read_body(response).await
+            }
+        };
+
+        let light = background(&preview("/preview/dog/200").await);
+        let dark = background(&preview("/preview/dog/200?scheme=dark").await);
+        assert_eq!(light, "#ffffff");
+        assert_ne!(dark, light);
+        assert_eq!(
+            background(&preview("/preview/dog/200?scheme=light").await),
+            light
+        );
+    }
+
+    #[tokio::test]
+    async fn preview_image_subdomain_returns_svg() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/preview/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
         assert_eq!(
             response
                 .headers()
@@ -1470,19 +3833,13 @@ mod tests {
         state.write_test_image("dog", 200);
         let info = STATUS_CODES.get(&200).expect("status info");
 
-        let request = Request::builder()
-            .method("GET")
-            .uri("/info/200")
-            .header("host", &format!("dog.{}", TEST_BASE_DOMAIN))
-            .body(Body::empty())
-            .expect("create request");
-        let response = app.oneshot(request).await.expect("send request");
+        let response = request_on_subdomain(&app, "dog", "GET", "/info/200").await;
         assert_eq!(response.status(), StatusCode::OK);
 
         let body = read_body(response).await;
-        assert!(body.contains(&info.name));
+        assert!(body.contains(&*info.name));
         let decoded_body = decode_html_entities(&body);
-        assert!(decoded_body.contains(&info.summary));
+        assert!(decoded_body.contains(&*info.summary));
         assert!(body.contains("/dog/200"));
     }
 
@@ -1530,13 +3887,35 @@ mod tests {
     #[tokio::test]
     async fn admin_update_toggles_enabled() {
         let (state, app) = get_test_app().await;
+        let (csrf_token, cookie) = admin_csrf_session(&app).await;
 
+        // without the session's token nothing changes
         let request = Request::builder()
             .method("POST")
             .uri("/admin/pets/otter")
             .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
             .header("host", TEST_BASE_DOMAIN)
-            .body(Body::from("status=enabled"))
+            .header("cookie", &cookie)
+            .body(Body::from("status=enabled&csrf_token=wrong"))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(
+            pets::Entity::find_by_name(state.db.as_ref(), "otter")
+                .await
+                .expect("fetch pet")
+                .is_none()
+        );
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/pets/otter")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::from(format!(
+                "status=enabled&csrf_token={csrf_token}"
+            )))
             .expect("create request");
         let response = app.oneshot(request).await.expect("send request");
         assert_eq!(response.status(), StatusCode::SEE_OTHER);
@@ -1553,6 +3932,85 @@ mod tests {
         assert!(enabled.contains(&"otter".to_string()));
     }
 
+    #[tokio::test]
+    async fn admin_update_blocks_enabled_to_submitted_unless_forced() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("otter", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        let (csrf_token, cookie) = admin_csrf_session(&app).await;
+        let update = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/admin/pets/otter")
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .body(Body::from(format!("{body}&csrf_token={csrf_token}")))
+                .expect("create request")
+        };
+        let otter_status = || async {
+            pets::Entity::find_by_name(state.db.as_ref(), "otter")
+                .await
+                .expect("fetch pet")
+                .expect("pet exists")
+                .status
+        };
+
+        // allowed transitions go straight through
+        let response = app
+            .clone()
+            .oneshot(update("status=voting"))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(otter_status().await, pets::PetStatus::Voting);
+        state
+            .create_or_update_pet("otter", pets::PetStatus::Enabled)
+            .await
+            .expect("re-enable pet");
+
+        let response = app
+            .clone()
+            .oneshot(update("status=submitted&votable=false"))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(otter_status().await, pets::PetStatus::Enabled);
+        // the blocked status change doesn't drop the voting change posted with it
+        let otter = pets::Entity::find_by_name(state.db.as_ref(), "otter")
+            .await
+            .expect("fetch pet")
+            .expect("pet exists");
+        assert!(!otter.votable);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let body = read_body(response).await;
+        assert!(body.contains("Tick force to do it anyway."));
+
+        let response = app
+            .oneshot(update("status=submitted&force=on"))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(otter_status().await, pets::PetStatus::Submitted);
+        assert!(
+            !state
+                .enabled_pets
+                .read()
+                .await
+                .contains(&"otter".to_string())
+        );
+    }
+
     #[tokio::test]
     async fn admin_create_pet_adds_dog() {
         let (state, app) = get_test_app().await;
@@ -1571,18 +4029,1059 @@ mod tests {
             .filter(pets::Column::Name.eq("dog"))
             .one(state.db.as_ref())
             .await
-            .expect("fetch pet")
-            .expect("pet exists");
-        assert_eq!(pet.status, pets::PetStatus::Enabled);
+            .expect("fetch pet")
+            .expect("pet exists");
+        assert_eq!(pet.status, pets::PetStatus::Enabled);
+    }
+
+    #[tokio::test]
+    async fn admin_upload_rejects_formats_not_allowed() {
+        let (state, app) = get_test_app().await;
+        assert_eq!(
+            state.config.upload_formats,
+            vec![UploadFormat::Jpeg, UploadFormat::Png]
+        );
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        // there's no BMP encoder compiled in, but a 1x1 bitmap is only headers and one pixel
+        let mut bmp = b"BM".to_vec();
+        for value in [58u32, 0, 54, 40, 1, 1] {
+            bmp.extend_from_slice(&value.to_le_bytes());
+        }
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&24u16.to_le_bytes());
+        for value in [0u32, 4, 2835, 2835, 0, 0] {
+            bmp.extend_from_slice(&value.to_le_bytes());
+        }
+        bmp.extend_from_slice(&[0, 0, 255, 0]);
+        assert_eq!(
+            image::guess_format(&bmp).ok(),
+            Some(image::ImageFormat::Bmp)
+        );
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(&mut png, image::ImageFormat::Png)
+            .expect("encode image");
+        let upload = |status_code: &str, image: Vec<u8>| {
+            let boundary = "boundaryformats";
+            let body = multipart_body(
+                boundary,
+                vec![
+                    ("pet", b"dog".to_vec(), None),
+                    ("status_code", status_code.as_bytes().to_vec(), None),
+                    ("csrf_token", csrf_token.clone().into_bytes(), None),
+                    ("redirect_to", b"/admin/pets/dog".to_vec(), None),
+                    ("image", image, Some("upload")),
+                ],
+            );
+            Request::builder()
+                .method("POST")
+                .uri("/admin/images")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .expect("create request")
+        };
+
+        let response = app
+            .clone()
+            .oneshot(upload("200", bmp))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response
+                .headers()
+                .get("location")
+                .expect("missing location"),
+            "/admin/pets/dog/status/200"
+        );
+        assert!(!state.image_path("dog", 200).exists());
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/pets/dog/status/200")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let body = decode_html_entities(&read_body(response).await).into_owned();
+        assert!(
+            body.contains("That image format isn't accepted for uploads."),
+            "{body}"
+        );
+
+        let response = app
+            .clone()
+            .oneshot(upload("201", png.into_inner()))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response
+                .headers()
+                .get("location")
+                .expect("missing location"),
+            "/admin/pets/dog"
+        );
+        assert!(state.image_path("dog", 201).exists());
+    }
+
+    #[tokio::test]
+    async fn admin_upload_saves_jpeg() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let mut cookie = cookie.expect("missing session cookie");
+
+        let boundary = "boundary123";
+        let jpeg_bytes =
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg")).to_vec();
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("pet", b"dog".to_vec(), None),
+                ("status_code", b"201".to_vec(), None),
+                ("csrf_token", csrf_token.into_bytes(), None),
+                ("image", jpeg_bytes, Some("dog.jpg")),
+            ],
+        );
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, new_cookie) = read_body_and_cookie(response).await;
+        if let Some(new_cookie) = new_cookie {
+            cookie = new_cookie;
+        }
+        assert!(body.contains("Upload successful"));
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        let body = read_body(response).await;
+        assert!(!body.contains("Upload successful"));
+
+        let image_path = state.image_dir.join("dog/201.jpg");
+        let metadata = tokio::fs::metadata(&image_path)
+            .await
+            .expect("uploaded file metadata");
+        assert!(metadata.is_file());
+    }
+
+    #[tokio::test]
+    async fn admin_upload_converts_png_to_jpeg() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let image = image::RgbImage::from_fn(1, 1, |_, _| image::Rgb([255, 0, 0]));
+        let mut png_bytes = Vec::new();
+        let encoder = image::codecs::png::PngEncoder::new(&mut png_bytes);
+        image::ImageEncoder::write_image(
+            encoder,
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .expect("encode png");
+
+        let boundary = "boundary456";
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("pet", b"dog".to_vec(), None),
+                ("status_code", b"202".to_vec(), None),
+                ("csrf_token", csrf_token.into_bytes(), None),
+                ("image", png_bytes, Some("dog.png")),
+            ],
+        );
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let image_path = state.image_dir.join("dog/202.jpg");
+        let bytes = tokio::fs::read(&image_path)
+            .await
+            .expect("read converted image");
+        let format = image::guess_format(&bytes).expect("guess format");
+        assert_eq!(format, image::ImageFormat::Jpeg);
+    }
+
+    #[tokio::test]
+    async fn admin_onboard_creates_pet_and_writes_image() {
+        let (state, app) = get_test_app().await;
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        assert!(body.contains("action=\"/admin/onboard\""));
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let mut png_bytes = Vec::new();
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png_bytes),
+                image::ImageFormat::Png,
+            )
+            .expect("encode png");
+        let boundary = "boundary246";
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("name", b"otters".to_vec(), None),
+                ("status", b"enabled".to_vec(), None),
+                ("status_code", b"201".to_vec(), None),
+                ("csrf_token", csrf_token.into_bytes(), None),
+                ("image", png_bytes, Some("otter.png")),
+            ],
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/onboard")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get("location").expect("location header"),
+            "/admin/pets/otter"
+        );
+
+        let pet = pets::Entity::find_by_name(state.db.as_ref(), "otter")
+            .await
+            .expect("fetch pet")
+            .expect("pet exists");
+        assert_eq!(pet.status, pets::PetStatus::Enabled);
+        let image_path = state.image_dir.join("otter").join("201.jpg");
+        let written = std::fs::read(&image_path).expect("image written");
+        assert_eq!(
+            image::guess_format(&written).expect("known format"),
+            image::ImageFormat::Jpeg
+        );
+
+        // the pet is enabled straight away, so its image is served
+        let response = request_on_subdomain(&app, "otter", "GET", "/201").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn admin_onboard_respects_status_transition_policy() {
+        let (state, app) = get_test_app().await;
+        pets::Entity::upsert_status(state.db.as_ref(), "otter", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let mut png_bytes = Vec::new();
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png_bytes),
+                image::ImageFormat::Png,
+            )
+            .expect("encode png");
+        let onboard = |force: bool| {
+            let boundary = "boundary247";
+            let mut fields = vec![
+                ("name", b"otter".to_vec(), None),
+                ("status", b"submitted".to_vec(), None),
+                ("status_code", b"201".to_vec(), None),
+                ("csrf_token", csrf_token.clone().into_bytes(), None),
+                ("image", png_bytes.clone(), Some("otter.png")),
+            ];
+            if force {
+                fields.push(("force", b"on".to_vec(), None));
+            }
+            Request::builder()
+                .method("POST")
+                .uri("/admin/onboard")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(multipart_body(boundary, fields)))
+                .expect("create request")
+        };
+
+        // hiding an enabled pet is blocked without force, and nothing is written
+        let response = app
+            .clone()
+            .oneshot(onboard(false))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get("location").expect("location header"),
+            "/admin/"
+        );
+        let pet = pets::Entity::find_by_name(state.db.as_ref(), "otter")
+            .await
+            .expect("fetch pet")
+            .expect("pet exists");
+        assert_eq!(pet.status, pets::PetStatus::Enabled);
+        let image_path = state.image_dir.join("otter").join("201.jpg");
+        assert!(!image_path.exists());
+
+        let response = app
+            .clone()
+            .oneshot(onboard(true))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get("location").expect("location header"),
+            "/admin/pets/otter"
+        );
+        let pet = pets::Entity::find_by_name(state.db.as_ref(), "otter")
+            .await
+            .expect("fetch pet")
+            .expect("pet exists");
+        assert_eq!(pet.status, pets::PetStatus::Submitted);
+        assert!(image_path.exists());
+    }
+
+    #[tokio::test]
+    async fn admin_onboard_holds_existing_image_for_confirmation() {
+        let (state, app) = get_test_app().await;
+        pets::Entity::upsert_status(state.db.as_ref(), "otter", pets::PetStatus::Submitted)
+            .await
+            .expect("create pet");
+        let image_path = state.write_test_image("otter", 201);
+        let original = std::fs::read(&image_path).expect("read image");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let mut png_bytes = Vec::new();
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png_bytes),
+                image::ImageFormat::Png,
+            )
+            .expect("encode png");
+        let boundary = "boundary248";
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("name", b"otter".to_vec(), None),
+                ("status", b"enabled".to_vec(), None),
+                ("status_code", b"201".to_vec(), None),
+                ("csrf_token", csrf_token.into_bytes(), None),
+                ("image", png_bytes, Some("otter.png")),
+            ],
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/onboard")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get("location").expect("location header"),
+            "/admin/pets/otter/status/201"
+        );
+
+        // neither the image nor the pet changed
+        assert_eq!(std::fs::read(&image_path).expect("read image"), original);
+        let pet = pets::Entity::find_by_name(state.db.as_ref(), "otter")
+            .await
+            .expect("fetch pet")
+            .expect("pet exists");
+        assert_eq!(pet.status, pets::PetStatus::Submitted);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/pets/otter/status/201")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = read_body(response).await;
+        assert!(body.contains("Confirm overwrite to continue."));
+        assert!(body.contains("src=\"/admin/pending-upload\""));
+    }
+
+    #[tokio::test]
+    async fn admin_upload_streams_large_image_to_disk() {
+        // noisy pixels keep the PNG from compressing, so it's a few megabytes on the wire
+        let image = image::RgbImage::from_fn(1200, 900, |x, y| {
+            let seed = x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503);
+            image::Rgb([seed as u8, (seed >> 8) as u8, (seed >> 16) as u8])
+        });
+        let mut png_bytes = Vec::new();
+        let encoder = image::codecs::png::PngEncoder::new(&mut png_bytes);
+        image::ImageEncoder::write_image(
+            encoder,
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .expect("encode png");
+        assert!(png_bytes.len() > 1024 * 1024);
+
+        for (max_upload_bytes, expected_status) in [
+            (8 * 1024 * 1024, StatusCode::SEE_OTHER),
+            (1024 * 1024, StatusCode::PAYLOAD_TOO_LARGE),
+        ] {
+            let (state, app) =
+                get_test_app_with(|state| state.config_mut().max_upload_bytes = max_upload_bytes)
+                    .await;
+            state
+                .create_or_update_pet("dog", pets::PetStatus::Enabled)
+                .await
+                .expect("create pet");
+
+            let request = Request::builder()
+                .method("GET")
+                .uri("/admin/")
+                .header("host", TEST_BASE_DOMAIN)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            let (body, cookie) = read_body_and_cookie(response).await;
+            let csrf_token = extract_csrf_token(&body);
+            let cookie = cookie.expect("missing session cookie");
+
+            let boundary = "boundary789";
+            let body = multipart_body(
+                boundary,
+                vec![
+                    ("pet", b"dog".to_vec(), None),
+                    ("status_code", b"200".to_vec(), None),
+                    ("csrf_token", csrf_token.into_bytes(), None),
+                    ("image", png_bytes.clone(), Some("dog.png")),
+                ],
+            );
+            let request = Request::builder()
+                .method("POST")
+                .uri("/admin/images")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .expect("create request");
+            let response = app.oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), expected_status);
+
+            let image_path = state.image_dir.join("dog/200.jpg");
+            if expected_status != StatusCode::SEE_OTHER {
+                assert!(!image_path.exists());
+                continue;
+            }
+            let bytes = tokio::fs::read(&image_path)
+                .await
+                .expect("read normalized image");
+            let saved = image::load_from_memory(&bytes).expect("decode normalized image");
+            assert_eq!((saved.width(), saved.height()), (1200, 900));
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_upload_over_limit_explains_the_limit() {
+        let (state, app) =
+            get_test_app_with(|state| state.config_mut().max_upload_bytes = 1024).await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let boundary = "boundary413";
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("pet", b"dog".to_vec(), None),
+                ("status_code", b"200".to_vec(), None),
+                ("csrf_token", csrf_token.into_bytes(), None),
+                ("image", vec![0u8; 4096], Some("dog.png")),
+            ],
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = read_body(response).await;
+        assert!(body.contains("1024 bytes"), "{body}");
+        assert!(!state.image_dir.join("dog/200.jpg").exists());
+
+        // past the whole request's limit it's that limit which is quoted
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("pet", b"dog".to_vec(), None),
+                ("image", vec![0u8; 1024 * 1024], Some("dog.png")),
+            ],
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = read_body(response).await;
+        let body_limit = spooled_upload::upload_body_limit(1024);
+        assert!(body.contains(&format!("{body_limit} bytes")), "{body}");
+        assert!(!state.image_dir.join("dog/200.jpg").exists());
+    }
+
+    #[tokio::test]
+    async fn admin_upload_rejects_oversized_text_fields() {
+        let (state, app) =
+            get_test_app_with(|state| state.config_mut().max_upload_bytes = 1024).await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let mut png_bytes = Vec::new();
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png_bytes),
+                image::ImageFormat::Png,
+            )
+            .expect("encode png");
+        // one text field just over the field cap, and one past the whole request's limit
+        for (redirect_to, expected_status) in [
+            (
+                vec![b'a'; crate::constants::UPLOAD_TEXT_FIELD_MAX_BYTES + 1],
+                StatusCode::BAD_REQUEST,
+            ),
+            (vec![b'a'; 1024 * 1024], StatusCode::PAYLOAD_TOO_LARGE),
+        ] {
+            let boundary = "boundarytext";
+            let body = multipart_body(
+                boundary,
+                vec![
+                    ("pet", b"dog".to_vec(), None),
+                    ("status_code", b"200".to_vec(), None),
+                    ("csrf_token", csrf_token.clone().into_bytes(), None),
+                    ("redirect_to", redirect_to, None),
+                    ("image", png_bytes.clone(), Some("dog.png")),
+                ],
+            );
+            let request = Request::builder()
+                .method("POST")
+                .uri("/admin/images")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), expected_status);
+            assert!(!state.image_dir.join("dog/200.jpg").exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_upload_requires_overwrite_confirmation() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 201);
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let jpeg_bytes =
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg")).to_vec();
+
+        let boundary = "boundary789";
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("pet", b"dog".to_vec(), None),
+                ("status_code", b"201".to_vec(), None),
+                ("csrf_token", csrf_token.clone().into_bytes(), None),
+                ("image", jpeg_bytes.clone(), Some("dog.jpg")),
+            ],
+        );
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response
+            .headers()
+            .get("location")
+            .expect("missing location header")
+            .to_str()
+            .expect("invalid location header");
+        assert!(location.ends_with("/admin/pets/dog/status/201"));
+
+        let original_bytes = tokio::fs::read(state.image_dir.join("dog/201.jpg"))
+            .await
+            .expect("read existing image");
+
+        let boundary = "boundary790";
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("pet", b"dog".to_vec(), None),
+                ("status_code", b"201".to_vec(), None),
+                ("csrf_token", csrf_token.into_bytes(), None),
+                ("overwrite", b"on".to_vec(), None),
+                ("image", jpeg_bytes, Some("dog.jpg")),
+            ],
+        );
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let new_bytes = tokio::fs::read(state.image_dir.join("dog/201.jpg"))
+            .await
+            .expect("read overwritten image");
+        assert_ne!(original_bytes, new_bytes);
+    }
+
+    #[tokio::test]
+    async fn replaced_jpeg_is_not_shadowed_by_stale_webp() {
+        let (state, app) = get_test_app().await;
+        for pet in ["dog", "cat"] {
+            state
+                .create_or_update_pet(pet, pets::PetStatus::Enabled)
+                .await
+                .expect("create pet");
+        }
+        state.write_test_image("dog", 201);
+        state.write_test_image("cat", 201);
+        for pet in ["dog", "cat"] {
+            std::fs::write(
+                state.image_path_for_format(pet, 201, ImageFormat::Webp),
+                b"RIFF\x00\x00\x00\x00WEBP",
+            )
+            .expect("write webp variant");
+        }
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let jpeg_bytes =
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg")).to_vec();
+        let boundary = "boundary505";
+        let body = multipart_body(
+            boundary,
+            vec![
+                ("pet", b"dog".to_vec(), None),
+                ("status_code", b"201".to_vec(), None),
+                ("csrf_token", csrf_token.clone().into_bytes(), None),
+                ("overwrite", b"on".to_vec(), None),
+                ("image", jpeg_bytes, Some("dog.jpg")),
+            ],
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/admin/images/copy")
+            .header("host", TEST_BASE_DOMAIN)
+            .header("cookie", &cookie)
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(format!(
+                "csrf_token={csrf_token}&pet=dog&status_code=201&target_pet=cat&target_status_code=201&overwrite=on"
+            )))
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        for pet in ["dog", "cat"] {
+            assert!(
+                !state
+                    .image_path_for_format(pet, 201, ImageFormat::Webp)
+                    .exists()
+            );
+            let request = Request::builder()
+                .method("GET")
+                .uri(format!("/{pet}/201"))
+                .header("host", TEST_BASE_DOMAIN)
+                .header(ACCEPT, "image/avif,image/webp,*/*;q=0.8")
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.headers().get(CONTENT_TYPE).expect("content type"),
+                "image/jpeg",
+                "{pet}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_upload_overwrite_shows_pending_comparison() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        let existing_path = state.write_test_image("dog", 201);
+        let original_bytes = std::fs::read(&existing_path).expect("read existing image");
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/admin/")
+            .header("host", TEST_BASE_DOMAIN)
+            .body(Body::empty())
+            .expect("create request");
+        let response = app.clone().oneshot(request).await.expect("send request");
+        let (body, cookie) = read_body_and_cookie(response).await;
+        let csrf_token = extract_csrf_token(&body);
+        let cookie = cookie.expect("missing session cookie");
+
+        let jpeg_bytes =
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg")).to_vec();
+        for action in ["cancel", "confirm"] {
+            let boundary = "boundarypending";
+            let body = multipart_body(
+                boundary,
+                vec![
+                    ("pet", b"dog".to_vec(), None),
+                    ("status_code", b"201".to_vec(), None),
+                    ("csrf_token", csrf_token.clone().into_bytes(), None),
+                    ("image", jpeg_bytes.clone(), Some("dog.jpg")),
+                ],
+            );
+            let request = Request::builder()
+                .method("POST")
+                .uri("/admin/images")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+            let request = Request::builder()
+                .method("GET")
+                .uri("/admin/pets/dog/status/201")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            let body = read_body(response).await;
+            assert!(body.contains(r#"src="/admin/pets/dog/images/201""#));
+            assert!(body.contains(r#"src="/admin/pending-upload""#));
+            assert!(body.contains("/admin/pending-upload/confirm"));
+            assert!(body.contains("/admin/pending-upload/cancel"));
+
+            let request = Request::builder()
+                .method("GET")
+                .uri("/admin/pending-upload")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.headers().get(CONTENT_TYPE).expect("content type"),
+                "image/jpeg"
+            );
+            assert_eq!(
+                std::fs::read(&existing_path).expect("read existing image"),
+                original_bytes
+            );
+
+            let request = Request::builder()
+                .method("POST")
+                .uri(format!("/admin/pending-upload/{action}"))
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .body(Body::from(format!("csrf_token={csrf_token}")))
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+            let request = Request::builder()
+                .method("GET")
+                .uri("/admin/pending-upload")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .body(Body::empty())
+                .expect("create request");
+            let response = app.clone().oneshot(request).await.expect("send request");
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let current = std::fs::read(&existing_path).expect("read image");
+            if action == "cancel" {
+                assert_eq!(current, original_bytes);
+            } else {
+                assert_ne!(current, original_bytes);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn image_cache_picks_up_files_changed_out_of_band() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
+            .await
+            .expect("create pet");
+        let path = state.write_test_image("dog", 200);
+
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // a sync replacing the file is served without /admin/reload-images
+        let synced = [0xFF, 0xD8, 0x01, 0x02, 0xFF, 0xD9];
+        std::fs::write(&path, synced).expect("replace image");
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response
+            .into_body()
+            .collect()
+            .await
+            .expect("collect body")
+            .to_bytes();
+        assert_eq!(bytes.as_ref(), synced);
+
+        // misses aren't remembered, so a missing code shows up once it's synced
+        for _ in 0..3 {
+            let response = request_on_subdomain(&app, "dog", "GET", "/201").await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+        assert_eq!(state.image_cache.len().await, 1);
+        std::fs::write(path.with_file_name("201.jpg"), synced).expect("sync image");
+        let response = request_on_subdomain(&app, "dog", "GET", "/201").await;
+        assert_eq!(response.status(), StatusCode::OK);
     }
 
     #[tokio::test]
-    async fn admin_upload_saves_jpeg() {
+    async fn reload_images_rereads_cached_images() {
         let (state, app) = get_test_app().await;
         state
             .create_or_update_pet("dog", pets::PetStatus::Enabled)
             .await
             .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        for _ in 0..2 {
+            let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(state.image_cache.loads(), 1);
 
         let request = Request::builder()
             .method("GET")
@@ -1593,74 +5092,47 @@ mod tests {
         let response = app.clone().oneshot(request).await.expect("send request");
         let (body, cookie) = read_body_and_cookie(response).await;
         let csrf_token = extract_csrf_token(&body);
-        let mut cookie = cookie.expect("missing session cookie");
-
-        let boundary = "boundary123";
-        let jpeg_bytes =
-            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg")).to_vec();
-        let body = multipart_body(
-            boundary,
-            vec![
-                ("pet", b"dog".to_vec(), None),
-                ("status_code", b"201".to_vec(), None),
-                ("csrf_token", csrf_token.into_bytes(), None),
-                ("image", jpeg_bytes, Some("dog.jpg")),
-            ],
-        );
+        let cookie = cookie.expect("missing session cookie");
 
         let request = Request::builder()
             .method("POST")
-            .uri("/admin/images")
+            .uri("/admin/reload-images")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
             .header("host", TEST_BASE_DOMAIN)
             .header("cookie", &cookie)
-            .header(
-                CONTENT_TYPE,
-                format!("multipart/form-data; boundary={boundary}"),
-            )
-            .body(Body::from(body))
+            .body(Body::from("csrf_token=invalid"))
             .expect("create request");
         let response = app.clone().oneshot(request).await.expect("send request");
-        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 
         let request = Request::builder()
-            .method("GET")
-            .uri("/admin/")
+            .method("POST")
+            .uri("/admin/reload-images")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
             .header("host", TEST_BASE_DOMAIN)
             .header("cookie", &cookie)
-            .body(Body::empty())
+            .body(Body::from(format!("csrf_token={csrf_token}")))
             .expect("create request");
         let response = app.clone().oneshot(request).await.expect("send request");
-        let (body, new_cookie) = read_body_and_cookie(response).await;
-        if let Some(new_cookie) = new_cookie {
-            cookie = new_cookie;
-        }
-        assert!(body.contains("Upload successful"));
-
-        let request = Request::builder()
-            .method("GET")
-            .uri("/admin/")
-            .header("host", TEST_BASE_DOMAIN)
-            .header("cookie", &cookie)
-            .body(Body::empty())
-            .expect("create request");
-        let response = app.oneshot(request).await.expect("send request");
-        let body = read_body(response).await;
-        assert!(!body.contains("Upload successful"));
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
 
-        let image_path = state.image_dir.join("dog/201.jpg");
-        let metadata = tokio::fs::metadata(&image_path)
-            .await
-            .expect("uploaded file metadata");
-        assert!(metadata.is_file());
+        let response = request_on_subdomain(&app, "dog", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.image_cache.loads(), 2);
     }
 
     #[tokio::test]
-    async fn admin_upload_converts_png_to_jpeg() {
+    async fn admin_copy_image_to_another_pet() {
         let (state, app) = get_test_app().await;
-        state
-            .create_or_update_pet("dog", pets::PetStatus::Enabled)
-            .await
-            .expect("create pet");
+        for pet in ["dog", "cat"] {
+            state
+                .create_or_update_pet(pet, pets::PetStatus::Enabled)
+                .await
+                .expect("create pet");
+        }
+        let source_path = state.write_test_image("dog", 200);
+        let target_path = state.image_path("cat", 200);
+        assert!(!target_path.exists());
 
         let request = Request::builder()
             .method("GET")
@@ -1673,140 +5145,157 @@ mod tests {
         let csrf_token = extract_csrf_token(&body);
         let cookie = cookie.expect("missing session cookie");
 
-        let image = image::RgbImage::from_fn(1, 1, |_, _| image::Rgb([255, 0, 0]));
-        let mut png_bytes = Vec::new();
-        let encoder = image::codecs::png::PngEncoder::new(&mut png_bytes);
-        image::ImageEncoder::write_image(
-            encoder,
-            image.as_raw(),
-            image.width(),
-            image.height(),
-            image::ExtendedColorType::Rgb8,
-        )
-        .expect("encode png");
+        let copy_request = |target_pet: &str, target_status_code: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/admin/images/copy")
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .header("host", TEST_BASE_DOMAIN)
+                .header("cookie", &cookie)
+                .body(Body::from(format!(
+                    "csrf_token={csrf_token}&pet=dog&status_code=200&target_pet={target_pet}&target_status_code={target_status_code}"
+                )))
+                .expect("create request")
+        };
 
-        let boundary = "boundary456";
-        let body = multipart_body(
-            boundary,
-            vec![
-                ("pet", b"dog".to_vec(), None),
-                ("status_code", b"202".to_vec(), None),
-                ("csrf_token", csrf_token.into_bytes(), None),
-                ("image", png_bytes, Some("dog.png")),
-            ],
+        let response = app
+            .clone()
+            .oneshot(copy_request("ferret", "200"))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(copy_request("cat", "600"))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(!target_path.exists());
+
+        let response = app
+            .clone()
+            .oneshot(copy_request("cat", "200"))
+            .await
+            .expect("send request");
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response
+                .headers()
+                .get("location")
+                .expect("missing location"),
+            "/admin/pets/cat"
         );
+        assert_eq!(
+            std::fs::read(&target_path).expect("read copied image"),
+            std::fs::read(&source_path).expect("read source image")
+        );
+
+        let response = request_on_subdomain(&app, "cat", "GET", "/200").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// Everything a handler could change: pets, votes and image files on disk.
+    async fn mutable_state_snapshot(
+        state: &AppState,
+    ) -> (Vec<pets::Model>, Vec<votes::Model>, Vec<PathBuf>) {
+        let pets = pets::Entity::find()
+            .all(state.db.as_ref())
+            .await
+            .expect("fetch pets");
+        let votes = votes::Entity::find()
+            .all(state.db.as_ref())
+            .await
+            .expect("fetch votes");
+        let mut images = Vec::new();
+        let mut dirs = vec![state.image_dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir).expect("read image dir") {
+                let path = entry.expect("read dir entry").path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    images.push(path);
+                }
+            }
+        }
+        images.sort();
+        (pets, votes, images)
+    }
 
+    /// Asserts a GET to `uri` on `host` doesn't record votes or change pets or images.
+    async fn assert_get_is_side_effect_free(app: &Router, state: &AppState, host: &str, uri: &str) {
+        let before = mutable_state_snapshot(state).await;
         let request = Request::builder()
-            .method("POST")
-            .uri("/admin/images")
-            .header("host", TEST_BASE_DOMAIN)
-            .header("cookie", &cookie)
-            .header(
-                CONTENT_TYPE,
-                format!("multipart/form-data; boundary={boundary}"),
-            )
-            .body(Body::from(body))
+            .method("GET")
+            .uri(uri)
+            .header("host", host)
+            .body(Body::empty())
             .expect("create request");
-        let response = app.oneshot(request).await.expect("send request");
-        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        app.clone().oneshot(request).await.expect("send request");
+        assert_eq!(
+            mutable_state_snapshot(state).await,
+            before,
+            "GET {uri} on {host} changed state"
+        );
+    }
 
-        let image_path = state.image_dir.join("dog/202.jpg");
-        let bytes = tokio::fs::read(&image_path)
+    #[tokio::test]
+    async fn get_routes_are_side_effect_free() {
+        let (state, app) = get_test_app().await;
+        state
+            .create_or_update_pet("dog", pets::PetStatus::Enabled)
             .await
-            .expect("read converted image");
-        let format = image::guess_format(&bytes).expect("guess format");
-        assert_eq!(format, image::ImageFormat::Jpeg);
+            .expect("create pet");
+        state
+            .create_or_update_pet("cat", pets::PetStatus::Voting)
+            .await
+            .expect("create pet");
+        state.write_test_image("dog", 200);
+
+        let pet_host = format!("dog.{TEST_BASE_DOMAIN}");
+        for (method, path) in ROUTES {
+            if *method != "GET" {
+                continue;
+            }
+            for pet in ["dog", "cat"] {
+                let uri = path
+                    .replace("{name}", pet)
+                    .replace("{pet}", pet)
+                    .replace("{segment}", pet)
+                    .replace("{status_code}", "200")
+                    .replace("{*wildcard}", "delete");
+                for host in [TEST_BASE_DOMAIN, pet_host.as_str()] {
+                    assert_get_is_side_effect_free(&app, &state, host, &uri).await;
+                }
+            }
+        }
     }
 
     #[tokio::test]
-    async fn admin_upload_requires_overwrite_confirmation() {
+    async fn get_delete_route_does_not_delete_pet() {
         let (state, app) = get_test_app().await;
         state
             .create_or_update_pet("dog", pets::PetStatus::Enabled)
             .await
             .expect("create pet");
-        state.write_test_image("dog", 201);
+        let image_path = state.write_test_image("dog", 200);
 
         let request = Request::builder()
             .method("GET")
-            .uri("/admin/")
+            .uri("/admin/pets/dog/delete")
             .header("host", TEST_BASE_DOMAIN)
             .body(Body::empty())
             .expect("create request");
-        let response = app.clone().oneshot(request).await.expect("send request");
-        let (body, cookie) = read_body_and_cookie(response).await;
-        let csrf_token = extract_csrf_token(&body);
-        let cookie = cookie.expect("missing session cookie");
-
-        let jpeg_bytes =
-            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/images/dog/100.jpg")).to_vec();
-
-        let boundary = "boundary789";
-        let body = multipart_body(
-            boundary,
-            vec![
-                ("pet", b"dog".to_vec(), None),
-                ("status_code", b"201".to_vec(), None),
-                ("csrf_token", csrf_token.clone().into_bytes(), None),
-                ("image", jpeg_bytes.clone(), Some("dog.jpg")),
-            ],
-        );
-
-        let request = Request::builder()
-            .method("POST")
-            .uri("/admin/images")
-            .header("host", TEST_BASE_DOMAIN)
-            .header("cookie", &cookie)
-            .header(
-                CONTENT_TYPE,
-                format!("multipart/form-data; boundary={boundary}"),
-            )
-            .body(Body::from(body))
-            .expect("create request");
-        let response = app.clone().oneshot(request).await.expect("send request");
-        assert_eq!(response.status(), StatusCode::SEE_OTHER);
-        let location = response
-            .headers()
-            .get("location")
-            .expect("missing location header")
-            .to_str()
-            .expect("invalid location header");
-        assert!(location.ends_with("/admin/pets/dog/status/201"));
-
-        let original_bytes = tokio::fs::read(state.image_dir.join("dog/201.jpg"))
-            .await
-            .expect("read existing image");
-
-        let boundary = "boundary790";
-        let body = multipart_body(
-            boundary,
-            vec![
-                ("pet", b"dog".to_vec(), None),
-                ("status_code", b"201".to_vec(), None),
-                ("csrf_token", csrf_token.into_bytes(), None),
-                ("overwrite", b"on".to_vec(), None),
-                ("image", jpeg_bytes, Some("dog.jpg")),
-            ],
-        );
-
-        let request = Request::builder()
-            .method("POST")
-            .uri("/admin/images")
-            .header("host", TEST_BASE_DOMAIN)
-            .header("cookie", &cookie)
-            .header(
-                CONTENT_TYPE,
-                format!("multipart/form-data; boundary={boundary}"),
-            )
-            .body(Body::from(body))
-            .expect("create request");
         let response = app.oneshot(request).await.expect("send request");
-        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.status(), StatusCode::OK);
 
-        let new_bytes = tokio::fs::read(state.image_dir.join("dog/201.jpg"))
+        let pet = pets::Entity::find_by_name(state.db.as_ref(), "dog")
             .await
-            .expect("read overwritten image");
-        assert_ne!(original_bytes, new_bytes);
+            .expect("fetch pet");
+        assert!(pet.is_some());
+        assert!(image_path.exists());
+        assert!(state.enabled_pets.read().await.contains(&"dog".to_string()));
     }
 
     #[tokio::test]
@@ -1910,4 +5399,63 @@ mod tests {
         .await
         .expect("insert pet");
     }
+
+    #[tokio::test]
+    async fn desynced_enabled_flag_is_reconciled() {
+        let (state, _app) = get_test_app().await;
+        for (name, status) in [
+            ("dog", pets::PetStatus::Enabled),
+            ("cat", pets::PetStatus::Voting),
+            ("owl", pets::PetStatus::Submitted),
+        ] {
+            state
+                .create_or_update_pet(name, status)
+                .await
+                .expect("create pet");
+        }
+        // simulate out-of-band edits that bypass the model
+        let db = state.db.as_ref();
+        db.execute_unprepared("UPDATE pets SET enabled = 0 WHERE name = 'dog'")
+            .await
+            .expect("desync dog");
+        db.execute_unprepared("UPDATE pets SET enabled = 1 WHERE name = 'cat'")
+            .await
+            .expect("desync cat");
+
+        assert_eq!(
+            pets::Entity::reconcile_enabled(db)
+                .await
+                .expect("reconcile"),
+            2
+        );
+        for pet in pets::Entity::find().all(db).await.expect("fetch pets") {
+            assert_eq!(
+                pet.enabled,
+                pet.status == pets::PetStatus::Enabled,
+                "{} is out of sync",
+                pet.name
+            );
+        }
+        assert_eq!(
+            pets::Entity::reconcile_enabled(db)
+                .await
+                .expect("reconcile"),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn healthz_skips_the_session_layer() {
+        let (_state, app) = get_test_app().await;
+
+        for path in ["/healthz", "/readyz"] {
+            let response = request_on_subdomain(&app, "www", "GET", path).await;
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+            assert!(
+                !response.headers().contains_key(SET_COOKIE),
+                "{path} should not create a session"
+            );
+            assert_eq!(read_body(response).await, "OK");
+        }
+    }
 }
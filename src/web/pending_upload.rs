@@ -0,0 +1,172 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+use tracing::{debug, error, info};
+
+use super::csrf::generate_token;
+use crate::error::HttpetError;
+
+const PENDING_UPLOAD_KEY: &str = "pending_upload";
+const PENDING_UPLOAD_DIR: &str = "httpet-pending-uploads";
+
+/// An upload waiting for the admin to confirm it should replace an existing image.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct PendingUpload {
+    pub(crate) pet: String,
+    pub(crate) status_code: u16,
+    token: String,
+}
+
+impl PendingUpload {
+    /// Where the pending image bytes are held until confirm/cancel.
+    pub(crate) fn path(&self) -> PathBuf {
+        pending_upload_dir().join(format!("{}.jpg", self.token))
+    }
+
+    pub(crate) fn matches(&self, pet: &str, status_code: u16) -> bool {
+        self.pet == pet && self.status_code == status_code
+    }
+}
+
+fn pending_upload_dir() -> PathBuf {
+    std::env::temp_dir().join(PENDING_UPLOAD_DIR)
+}
+
+/// Holds the upload in a temp file, replacing any previous pending upload for the session.
+pub(crate) async fn store(
+    session: &Session,
+    pet: &str,
+    status_code: u16,
+    bytes: &[u8],
+) -> Result<(), HttpetError> {
+    discard(session).await?;
+
+    let pending = PendingUpload {
+        pet: pet.to_string(),
+        status_code,
+        token: generate_token(),
+    };
+    let path = pending.path();
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, bytes).await?;
+    session
+        .insert(PENDING_UPLOAD_KEY, pending)
+        .await
+        .map_err(|err| HttpetError::InternalServerError(err.to_string()))
+}
+
+pub(crate) async fn get(session: &Session) -> Result<Option<PendingUpload>, HttpetError> {
+    session
+        .get::<PendingUpload>(PENDING_UPLOAD_KEY)
+        .await
+        .map_err(|err| HttpetError::InternalServerError(err.to_string()))
+}
+
+/// Removes the pending upload from the session, leaving the temp file to the caller.
+pub(crate) async fn take(session: &Session) -> Result<Option<PendingUpload>, HttpetError> {
+    session
+        .remove::<PendingUpload>(PENDING_UPLOAD_KEY)
+        .await
+        .map_err(|err| HttpetError::InternalServerError(err.to_string()))
+}
+
+/// Drops the pending upload and its temp file.
+pub(crate) async fn discard(session: &Session) -> Result<(), HttpetError> {
+    if let Some(pending) = take(session).await? {
+        remove_file(&pending).await?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn remove_file(pending: &PendingUpload) -> Result<(), HttpetError> {
+    match tokio::fs::remove_file(pending.path()).await {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(HttpetError::InternalServerError(err.to_string())),
+    }
+}
+
+/// Deletes pending uploads older than `max_age`; sessions live in memory, so expired or
+/// restarted sessions leave their temp files behind.
+pub(crate) async fn sweep_stale(max_age: Duration) -> usize {
+    sweep_stale_in(&pending_upload_dir(), max_age).await
+}
+
+async fn sweep_stale_in(dir: &Path, max_age: Duration) -> usize {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return 0,
+        Err(err) => {
+            error!(error=?err, dir=%dir.display(), "Failed to read pending upload dir");
+            return 0;
+        }
+    };
+    let now = SystemTime::now();
+    let mut removed = 0;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let Ok(modified) = entry
+            .metadata()
+            .await
+            .and_then(|metadata| metadata.modified())
+        else {
+            continue;
+        };
+        if now.duration_since(modified).unwrap_or_default() < max_age {
+            continue;
+        }
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => removed += 1,
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => {
+                error!(error=?err, path=%path.display(), "Failed to remove stale pending upload")
+            }
+        }
+    }
+    if removed > 0 {
+        info!(removed, "Swept stale pending uploads");
+    } else {
+        debug!("No stale pending uploads to sweep");
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sweep_removes_only_stale_uploads() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let stale = dir.path().join("stale.jpg");
+        let fresh = dir.path().join("fresh.jpg");
+        std::fs::write(&stale, b"old").expect("write stale upload");
+        std::fs::write(&fresh, b"new").expect("write fresh upload");
+        std::fs::File::options()
+            .write(true)
+            .open(&stale)
+            .and_then(|file| {
+                file.set_modified(SystemTime::now() - Duration::from_secs(2 * 60 * 60))
+            })
+            .expect("age stale upload");
+
+        let removed = sweep_stale_in(dir.path(), Duration::from_secs(60 * 60)).await;
+        assert_eq!(removed, 1);
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+    }
+
+    #[tokio::test]
+    async fn sweep_ignores_missing_dir() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        assert_eq!(
+            sweep_stale_in(&dir.path().join("missing"), Duration::ZERO).await,
+            0
+        );
+    }
+}
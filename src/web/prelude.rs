@@ -1,4 +1,6 @@
-pub(crate) use super::{normalize_pet_name, normalize_pet_name_strict};
+pub(crate) use super::{
+    StatusCodeParam, deserialize_status_code, normalize_pet_name, normalize_pet_name_strict,
+};
 pub(crate) use crate::error::HttpetError;
 pub(crate) use crate::status_codes::STATUS_CODES;
 pub(crate) use crate::{db, db::entities::votes::record_vote, web::AppState};
@@ -10,7 +12,7 @@ pub(crate) use axum::response::IntoResponse;
 pub(crate) use chrono::{Duration, Utc};
 pub(crate) use sea_orm::sea_query::{Alias, Expr, JoinType, Order, Query};
 pub(crate) use sea_orm::{
-    ActiveModelTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, Set, StatementBuilder,
+    ActiveModelTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, StatementBuilder,
 };
 pub(crate) use serde::Deserialize;
 pub(crate) use std::sync::Arc;
@@ -0,0 +1,31 @@
+//! Health probes, mounted outside the session and logging layers so frequent polling
+//! doesn't create sessions or fill the logs.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+use super::AppState;
+
+/// Liveness, answers as long as the server is accepting requests.
+pub(crate) async fn healthz() -> Response {
+    (StatusCode::OK, "OK").into_response()
+}
+
+/// Readiness, the database has to answer a ping within the probe timeout.
+pub(crate) async fn readyz(State(state): State<AppState>) -> Response {
+    match tokio::time::timeout(state.config.probe_timeout, state.db.ping()).await {
+        Ok(Ok(())) => (StatusCode::OK, "OK").into_response(),
+        Ok(Err(err)) => {
+            tracing::warn!(error=?err, "Readiness probe failed to ping the database");
+            (StatusCode::SERVICE_UNAVAILABLE, "Unavailable").into_response()
+        }
+        Err(_) => {
+            tracing::warn!(
+                timeout=?state.config.probe_timeout,
+                "Readiness probe timed out pinging the database"
+            );
+            (StatusCode::SERVICE_UNAVAILABLE, "Unavailable").into_response()
+        }
+    }
+}
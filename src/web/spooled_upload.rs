@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+
+use axum::extract::multipart::{Field, MultipartError};
+use axum::http::StatusCode;
+use tokio::io::AsyncWriteExt;
+
+use super::csrf::generate_token;
+use crate::constants::{UPLOAD_FORM_OVERHEAD_BYTES, UPLOAD_TEXT_FIELD_MAX_BYTES};
+use crate::error::HttpetError;
+
+const SPOOLED_UPLOAD_DIR: &str = "httpet-upload-spool";
+
+/// An uploaded file field written to a temp file, removed again when dropped.
+#[derive(Debug)]
+pub(crate) struct SpooledUpload {
+    path: PathBuf,
+    len: u64,
+}
+
+impl SpooledUpload {
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+impl Drop for SpooledUpload {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_file(&self.path)
+            && err.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!(error=?err, path=%self.path.display(), "Failed to remove spooled upload");
+        }
+    }
+}
+
+/// Streams a multipart field to a temp file chunk by chunk, so memory use doesn't
+/// grow with the upload. Fields over `max_bytes` are rejected part way through.
+pub(crate) async fn spool_field(
+    mut field: Field<'_>,
+    max_bytes: u64,
+) -> Result<SpooledUpload, HttpetError> {
+    let dir = std::env::temp_dir().join(SPOOLED_UPLOAD_DIR);
+    tokio::fs::create_dir_all(&dir).await?;
+    let mut spooled = SpooledUpload {
+        path: dir.join(format!("{}.upload", generate_token())),
+        len: 0,
+    };
+    let mut file = tokio::fs::File::create(&spooled.path).await?;
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|err| multipart_error(&err, max_bytes))?
+    {
+        spooled.len += chunk.len() as u64;
+        if spooled.len > max_bytes {
+            return Err(HttpetError::PayloadTooLarge { limit: max_bytes });
+        }
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(spooled)
+}
+
+/// The request body limit for upload forms: the image plus room for the text fields.
+pub(crate) fn upload_body_limit(max_upload_bytes: u64) -> u64 {
+    max_upload_bytes.saturating_add(UPLOAD_FORM_OVERHEAD_BYTES)
+}
+
+/// Reads a short text field from an upload form, rejecting it past
+/// `UPLOAD_TEXT_FIELD_MAX_BYTES` rather than buffering whatever was sent.
+pub(crate) async fn text_field(
+    mut field: Field<'_>,
+    max_upload_bytes: u64,
+) -> Result<String, HttpetError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|err| multipart_error(&err, max_upload_bytes))?
+    {
+        if bytes.len() + chunk.len() > UPLOAD_TEXT_FIELD_MAX_BYTES {
+            tracing::debug!(
+                field = field.name().unwrap_or_default(),
+                "Upload form text field too long"
+            );
+            return Err(HttpetError::BadRequest);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    String::from_utf8(bytes).map_err(|_| HttpetError::BadRequest)
+}
+
+/// Maps an error reading an upload form. The request body limit tripping shows up as a 413
+/// quoting that limit, since it's the one that applied rather than the image's own.
+pub(crate) fn multipart_error(err: &MultipartError, max_upload_bytes: u64) -> HttpetError {
+    if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        HttpetError::PayloadTooLarge {
+            limit: upload_body_limit(max_upload_bytes),
+        }
+    } else {
+        HttpetError::InternalServerError(err.to_string())
+    }
+}
@@ -2,12 +2,15 @@ use super::csrf;
 use super::prelude::*;
 use crate::{
     db::entities::{pets, votes},
-    web::{middleware::AnimalDomain, status_codes_for},
+    status_codes::{MDN_STATUS_URL, StatusCodes, StatusInfo, StatusSlugs},
+    web::{middleware::AnimalDomain, parse_status_code, status_codes_for},
 };
+use axum::extract::Query as UrlQuery;
 use axum::response::{Redirect, Response};
 use base64::Engine;
 use rand::prelude::IndexedRandom;
 use serde_json::json;
+use std::sync::LazyLock;
 use tokio::fs;
 
 #[derive(Template, WebTemplate)]
@@ -15,7 +18,9 @@ use tokio::fs;
 pub(crate) struct VotePageTemplate {
     pub(crate) name: String,
     pub(crate) csrf_token: String,
+    pub(crate) voting_closed: bool,
     pub(crate) frontend_url: String,
+    pub(crate) theme_stylesheet: Option<String>,
 }
 
 #[derive(Template, WebTemplate)]
@@ -23,20 +28,51 @@ pub(crate) struct VotePageTemplate {
 pub(crate) struct VoteThanksTemplate {
     pub(crate) name: String,
     pub(crate) frontend_url: String,
+    pub(crate) theme_stylesheet: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct TopPet {
     pub(crate) name: String,
     pub(crate) votes: i64,
+    /// Closed pets are still listed, just without a vote button.
+    pub(crate) votable: bool,
 }
 
+/// Listed for served codes without status metadata.
+static UNKNOWN_STATUS_INFO: LazyLock<StatusInfo> = LazyLock::new(|| StatusInfo {
+    name: "Unknown Status Code".into(),
+    summary: "There's no description for this status code.".into(),
+    mdn_url: MDN_STATUS_URL.into(),
+});
+
+/// A row in a pet's status list, sharing its text with the live metadata.
 #[derive(Clone, Debug)]
 pub(crate) struct StatusCodeEntry {
     pub(crate) code: u16,
-    pub(crate) name: String,
-    pub(crate) summary: String,
-    pub(crate) mdn_url: String,
+    pub(crate) name: Arc<str>,
+    pub(crate) summary: Arc<str>,
+    pub(crate) mdn_url: Arc<str>,
+}
+
+/// Builds list entries for `codes` from one read of the metadata; custom codes have
+/// images but no metadata, so they're listed generically.
+pub(crate) fn status_code_entries(
+    status_info: &StatusCodes,
+    codes: &[u16],
+) -> Vec<StatusCodeEntry> {
+    codes
+        .iter()
+        .map(|&code| {
+            let info = status_info.get(&code).unwrap_or(&UNKNOWN_STATUS_INFO);
+            StatusCodeEntry {
+                code,
+                name: Arc::clone(&info.name),
+                summary: Arc::clone(&info.summary),
+                mdn_url: Arc::clone(&info.mdn_url),
+            }
+        })
+        .collect()
 }
 
 #[derive(Template, WebTemplate)]
@@ -44,9 +80,13 @@ pub(crate) struct StatusCodeEntry {
 pub(crate) struct HomeTemplate {
     pub(crate) enabled_pets: Vec<db::entities::pets::Model>,
     pub(crate) top_pets: Vec<TopPet>,
+    pub(crate) all_time_top_pets: Vec<TopPet>,
     pub(crate) state: AppState,
+    /// Base domain the visitor is on, pet links stay on it.
+    pub(crate) base_domain: String,
     pub(crate) csrf_token: String,
     pub(crate) frontend_url: String,
+    pub(crate) theme_stylesheet: Option<String>,
 }
 
 #[derive(Template, WebTemplate)]
@@ -55,6 +95,10 @@ pub(crate) struct NotFoundTemplate {
     pub(crate) has_image: bool,
     pub(crate) image_url: String,
     pub(crate) frontend_url: String,
+    pub(crate) reason: Option<String>,
+    /// No pets are enabled yet, which is how a fresh deploy starts out.
+    pub(crate) no_pets: bool,
+    pub(crate) theme_stylesheet: Option<String>,
 }
 
 #[derive(Template, WebTemplate)]
@@ -62,6 +106,8 @@ pub(crate) struct NotFoundTemplate {
 pub(crate) struct AboutTemplate {
     pub(crate) frontend_url: String,
     pub(crate) pet_example_url: String,
+    pub(crate) subdomains: bool,
+    pub(crate) theme_stylesheet: Option<String>,
 }
 
 #[derive(Template, WebTemplate)]
@@ -69,9 +115,10 @@ pub(crate) struct AboutTemplate {
 pub(crate) struct StatusListTemplate {
     pub(crate) name: String,
     pub(crate) status_codes: Vec<StatusCodeEntry>,
-    pub(crate) base_domain: String,
+    pub(crate) pet_host: String,
     pub(crate) info_link_prefix: String,
     pub(crate) frontend_url: String,
+    pub(crate) theme_stylesheet: Option<String>,
 }
 
 #[derive(Template, WebTemplate)]
@@ -79,93 +126,164 @@ pub(crate) struct StatusListTemplate {
 pub(crate) struct StatusInfoTemplate {
     pub(crate) pet_name: String,
     pub(crate) status_code: u16,
-    pub(crate) status_name: String,
-    pub(crate) status_summary: String,
-    pub(crate) mdn_url: String,
+    pub(crate) status_name: Arc<str>,
+    pub(crate) status_summary: Arc<str>,
+    pub(crate) mdn_url: Arc<str>,
     pub(crate) image_url: String,
     pub(crate) page_url: String,
     pub(crate) preview_image_url: String,
     pub(crate) frontend_url: String,
+    pub(crate) theme_stylesheet: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub(crate) struct InfoPath {
     pub(crate) pet: String,
-    pub(crate) status_code: u16,
+    /// A numeric code, or a status name slug like `not-found`.
+    pub(crate) status_code: String,
 }
 
 #[derive(Deserialize)]
 pub(crate) struct PreviewPath {
     pub(crate) pet: String,
+    #[serde(deserialize_with = "deserialize_status_code")]
     pub(crate) status_code: u16,
 }
 
-pub(crate) async fn pet_status_list(state: AppState, pet: &str) -> Result<Response, HttpetError> {
-    pet_status_list_with_prefix(state, pet, format!("/info/{}", pet)).await
+/// Light or dark styling for preview cards.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PreviewScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl PreviewScheme {
+    fn palette(self) -> PreviewPalette {
+        match self {
+            Self::Light => PreviewPalette {
+                background: "#ffffff",
+                panel: "#ffffff",
+                border: "#e7e0ff",
+                code_text: "#2b145a",
+                name_text: "#5a3b8a",
+            },
+            Self::Dark => PreviewPalette {
+                background: "#120b24",
+                panel: "#1e1538",
+                border: "#3b2d66",
+                code_text: "#f3ecff",
+                name_text: "#cbb8f0",
+            },
+        }
+    }
+}
+
+/// Colours for the parts of a preview card.
+struct PreviewPalette {
+    background: &'static str,
+    panel: &'static str,
+    border: &'static str,
+    code_text: &'static str,
+    name_text: &'static str,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PreviewQuery {
+    pub(crate) scheme: Option<PreviewScheme>,
+}
+
+pub(crate) async fn pet_status_list(
+    state: AppState,
+    base_domain: &str,
+    pet: &str,
+) -> Result<Response, HttpetError> {
+    pet_status_list_with_prefix(state, base_domain, pet, format!("/info/{}", pet)).await
 }
 
 pub(crate) async fn pet_status_list_subdomain(
     state: AppState,
+    base_domain: &str,
     pet: &str,
 ) -> Result<Response, HttpetError> {
-    pet_status_list_with_prefix(state, pet, "/info".to_string()).await
+    pet_status_list_with_prefix(state, base_domain, pet, "/info".to_string()).await
 }
 
 async fn pet_status_list_with_prefix(
     state: AppState,
+    base_domain: &str,
     pet: &str,
     info_link_prefix: String,
 ) -> Result<Response, HttpetError> {
     let enabled = state.enabled_pets.read().await.contains(&pet.to_string());
     if !enabled {
-        return Err(HttpetError::NeedsVote(state.base_url(), pet.to_string()));
+        return Err(state
+            .pet_not_enabled(state.base_url_for(base_domain), pet)
+            .await);
     }
 
     let status_codes = status_codes_for(&state.image_dir, pet).await?;
-    let mut status_entries = Vec::with_capacity(status_codes.len());
-    for code in status_codes {
-        let Some(info) = STATUS_CODES.get(&code) else {
-            return Err(HttpetError::InternalServerError(format!(
-                "Missing metadata for status code {code}"
-            )));
-        };
-        status_entries.push(StatusCodeEntry {
-            code,
-            name: info.name.clone(),
-            summary: info.summary.clone(),
-            mdn_url: info.mdn_url.clone(),
-        });
-    }
+    let status_entries = status_code_entries(&state.status_codes.read().await.codes, &status_codes);
 
     Ok(StatusListTemplate {
         name: pet.to_string(),
         status_codes: status_entries,
-        base_domain: state.base_domain.clone(),
+        pet_host: state.pet_host(base_domain, pet),
         info_link_prefix,
-        frontend_url: frontend_url_for_state(&state),
+        frontend_url: state.base_url_for(base_domain),
+        theme_stylesheet: state.theme_stylesheet(),
     }
     .into_response())
 }
 
 pub(crate) async fn status_info_view(
+    domain: AnimalDomain,
     State(state): State<AppState>,
     Path(path): Path<InfoPath>,
 ) -> Result<Response, HttpetError> {
     let pet = normalize_pet_name_strict(&path.pet)?;
-    status_info_response(state, pet, path.status_code).await
+    match parse_status_code(&path.status_code) {
+        Some(status_code) => {
+            status_info_response(state, &domain.base_domain, pet, status_code).await
+        }
+        None => {
+            let status_info = state.status_codes.read().await;
+            status_slug_redirect(&status_info.slugs, &pet, &path.status_code)
+        }
+    }
+}
+
+/// Redirects a status name slug like `/info/dog/not-found` to the numeric info page.
+/// Temporary, since an admin can rename the status and move the slug to another code.
+fn status_slug_redirect(
+    slugs: &StatusSlugs,
+    pet: &str,
+    slug: &str,
+) -> Result<Response, HttpetError> {
+    match slugs.get(&slug.to_ascii_lowercase()).map(Vec::as_slice) {
+        Some([status_code]) => {
+            Ok(Redirect::temporary(&format!("/info/{pet}/{status_code}")).into_response())
+        }
+        Some(status_codes) => Err(HttpetError::NotFound(format!(
+            "{}",
+            json!({"status_name": slug, "ambiguous_status_codes": status_codes})
+        ))),
+        None => Err(HttpetError::BadRequest),
+    }
 }
 
 pub(crate) async fn status_info_view_subdomain(
     domain: AnimalDomain,
     State(state): State<AppState>,
-    Path(status_code): Path<u16>,
+    Path(StatusCodeParam(status_code)): Path<StatusCodeParam>,
 ) -> Result<Response, HttpetError> {
     if !(100..=599).contains(&status_code) {
         return Err(HttpetError::BadRequest);
     }
     if let Some(pet) = domain.animal {
         let pet = normalize_pet_name_strict(&pet)?;
-        return status_info_response(state, pet, status_code).await;
+        return status_info_response(state, &domain.base_domain, pet, status_code).await;
     }
 
     let Some(pet) = random_pet_with_status(&state, status_code).await? else {
@@ -190,40 +308,61 @@ pub(crate) async fn status_info_view_subdomain(
 }
 
 pub(crate) async fn info_shortcut_handler(
+    domain: AnimalDomain,
     State(state): State<AppState>,
-    Path(_status_code): Path<u16>,
+    Path(_status_code): Path<StatusCodeParam>,
 ) -> Result<Response, HttpetError> {
-    Ok(Redirect::to(&frontend_url_for_state(&state)).into_response())
+    Ok(Redirect::to(&state.base_url_for(&domain.base_domain)).into_response())
 }
 
 pub(crate) async fn preview_image_handler(
+    domain: AnimalDomain,
     State(state): State<AppState>,
     Path(path): Path<PreviewPath>,
+    UrlQuery(query): UrlQuery<PreviewQuery>,
 ) -> Result<Response, HttpetError> {
     let pet = normalize_pet_name_strict(&path.pet)?;
-    preview_image_response(state, pet, path.status_code).await
+    preview_image_response(
+        state,
+        &domain.base_domain,
+        pet,
+        path.status_code,
+        query.scheme.unwrap_or_default(),
+    )
+    .await
 }
 
 pub(crate) async fn preview_image_handler_subdomain(
     domain: AnimalDomain,
     State(state): State<AppState>,
-    Path(status_code): Path<u16>,
+    Path(StatusCodeParam(status_code)): Path<StatusCodeParam>,
+    UrlQuery(query): UrlQuery<PreviewQuery>,
 ) -> Result<Response, HttpetError> {
     let Some(pet) = domain.animal else {
         return Err(HttpetError::BadRequest);
     };
     let pet = normalize_pet_name_strict(&pet)?;
-    preview_image_response(state, pet, status_code).await
+    preview_image_response(
+        state,
+        &domain.base_domain,
+        pet,
+        status_code,
+        query.scheme.unwrap_or_default(),
+    )
+    .await
 }
 
 async fn status_info_response(
     state: AppState,
+    base_domain: &str,
     pet: String,
     status_code: u16,
 ) -> Result<Response, HttpetError> {
     let enabled = state.enabled_pets.read().await.contains(&pet);
     if !enabled {
-        return Err(HttpetError::NeedsVote(state.base_url(), pet));
+        return Err(state
+            .pet_not_enabled(state.base_url_for(base_domain), &pet)
+            .await);
     }
 
     let image_path = state.image_path(&pet, status_code);
@@ -240,11 +379,12 @@ async fn status_info_response(
         }
     }
 
-    let status_info = STATUS_CODES
-        .get(&status_code)
+    let status_info = state
+        .status_info(status_code)
+        .await
         .ok_or_else(|| HttpetError::NotFound(format!("{}", json!({"status_code": status_code}))))?;
 
-    let frontend_url = frontend_url_for_state(&state);
+    let frontend_url = state.base_url_for(base_domain);
 
     Ok(StatusInfoTemplate {
         pet_name: pet.clone(),
@@ -256,21 +396,40 @@ async fn status_info_response(
         page_url: format!("{}/info/{}/{}", frontend_url, pet, status_code),
         preview_image_url: format!("{}/preview/{}/{}", frontend_url, pet, status_code),
         frontend_url,
+        theme_stylesheet: state.theme_stylesheet(),
     }
     .into_response())
 }
 
+/// Scales `width` x `height` to fit inside a `max` square, keeping the aspect ratio.
+fn fit_within(width: u32, height: u32, max: u32) -> (u32, u32) {
+    if width == 0 || height == 0 {
+        return (max, max);
+    }
+    if width >= height {
+        let scaled = u64::from(height) * u64::from(max) / u64::from(width);
+        (max, u32::try_from(scaled).unwrap_or(max).max(1))
+    } else {
+        let scaled = u64::from(width) * u64::from(max) / u64::from(height);
+        (u32::try_from(scaled).unwrap_or(max).max(1), max)
+    }
+}
+
 async fn preview_image_response(
     state: AppState,
+    base_domain: &str,
     pet: String,
     status_code: u16,
+    scheme: PreviewScheme,
 ) -> Result<Response, HttpetError> {
     if !(100..=599).contains(&status_code) {
         return Err(HttpetError::BadRequest);
     }
     let enabled = state.enabled_pets.read().await.contains(&pet);
     if !enabled {
-        return Err(HttpetError::NeedsVote(state.base_url(), pet));
+        return Err(state
+            .pet_not_enabled(state.base_url_for(base_domain), &pet)
+            .await);
     }
 
     let image_path = state.image_path(&pet, status_code);
@@ -285,10 +444,15 @@ async fn preview_image_response(
         Err(err) => return Err(HttpetError::InternalServerError(err.to_string())),
     };
 
-    let status_info = STATUS_CODES
-        .get(&status_code)
+    let status_info = state
+        .status_info(status_code)
+        .await
         .ok_or_else(|| HttpetError::NotFound(format!("{}", json!({"status_code": status_code}))))?;
 
+    let image_dimensions = image::ImageReader::new(std::io::Cursor::new(&image_bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok());
     let image_base64 = base64::engine::general_purpose::STANDARD.encode(image_bytes);
     let image_href = format!("data:image/jpeg;base64,{}", image_base64);
     let status_name = html_escape::encode_text(&status_info.name);
@@ -297,12 +461,18 @@ async fn preview_image_response(
     let height = 630;
     let padding = 60;
     let pill_radius = 48;
-    let image_size = 420;
+    let image_slot = 420;
     let image_radius = 36;
-    let image_x = padding + 40;
-    let image_y = (height - image_size) / 2;
+    // size the frame to the image so portrait/landscape art isn't letterboxed
+    let (image_width, image_height) = match image_dimensions {
+        Some((source_width, source_height)) => fit_within(source_width, source_height, image_slot),
+        None => (image_slot, image_slot),
+    };
+    let image_slot_x = padding + 40;
+    let image_x = image_slot_x + (image_slot - image_width) / 2;
+    let image_y = (height - image_height) / 2;
     let gap = 60;
-    let right_start = image_x + image_size + gap;
+    let right_start = image_slot_x + image_slot + gap;
     let right_width = width - right_start - padding - 40;
     let text_center_x = right_start + right_width / 2;
     let code_box_width = right_width;
@@ -315,27 +485,34 @@ async fn preview_image_response(
     let name_box_x = right_start;
     let name_box_y = code_box_y + code_box_height + 30;
     let name_y = name_box_y + (name_box_height / 2) + 4;
+    let PreviewPalette {
+        background,
+        panel,
+        border,
+        code_text,
+        name_text,
+    } = scheme.palette();
 
     let svg = format!(
         r##"<?xml version="1.0" encoding="UTF-8"?>
 <svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">
-  <rect width="100%" height="100%" fill="#ffffff"/>
+  <rect width="100%" height="100%" fill="{background}"/>
   <defs>
     <linearGradient id="primary-gradient" x1="0%" y1="0%" x2="100%" y2="100%">
       <stop offset="0%" stop-color="#681dd8d8"/>
       <stop offset="100%" stop-color="#ab79e0"/>
     </linearGradient>
     <clipPath id="image-clip">
-      <rect x="{image_x}" y="{image_y}" width="{image_size}" height="{image_size}" rx="{image_radius}"/>
+      <rect x="{image_x}" y="{image_y}" width="{image_width}" height="{image_height}" rx="{image_radius}"/>
     </clipPath>
   </defs>
   <rect x="{padding}" y="{padding}" width="{width_minus_padding}" height="{height_minus_padding}" rx="{pill_radius}" fill="url(#primary-gradient)"/>
-  <rect x="{image_x}" y="{image_y}" width="{image_size}" height="{image_size}" rx="{image_radius}" fill="#ffffff" stroke="#e7e0ff" stroke-width="2"/>
-  <image x="{image_x}" y="{image_y}" width="{image_size}" height="{image_size}" preserveAspectRatio="xMidYMid meet" href="{image_href}" clip-path="url(#image-clip)"/>
-  <rect x="{code_box_x}" y="{code_box_y}" width="{code_box_width}" height="{code_box_height}" rx="28" fill="#ffffff" stroke="#e7e0ff" stroke-width="2"/>
-  <text x="{text_center_x}" y="{code_y}" text-anchor="middle" dominant-baseline="middle" font-family="system-ui, -apple-system, BlinkMacSystemFont, Segoe UI, sans-serif" font-size="96" font-weight="700" fill="#2b145a">{status_code}</text>
-  <rect x="{name_box_x}" y="{name_box_y}" width="{name_box_width}" height="{name_box_height}" rx="24" fill="#ffffff" stroke="#e7e0ff" stroke-width="2"/>
-  <text x="{text_center_x}" y="{name_y}" text-anchor="middle" dominant-baseline="middle" font-family="system-ui, -apple-system, BlinkMacSystemFont, Segoe UI, sans-serif" font-size="44" font-weight="600" fill="#5a3b8a">{status_name}</text>
+  <rect x="{image_x}" y="{image_y}" width="{image_width}" height="{image_height}" rx="{image_radius}" fill="{panel}" stroke="{border}" stroke-width="2"/>
+  <image x="{image_x}" y="{image_y}" width="{image_width}" height="{image_height}" preserveAspectRatio="xMidYMid meet" href="{image_href}" clip-path="url(#image-clip)"/>
+  <rect x="{code_box_x}" y="{code_box_y}" width="{code_box_width}" height="{code_box_height}" rx="28" fill="{panel}" stroke="{border}" stroke-width="2"/>
+  <text x="{text_center_x}" y="{code_y}" text-anchor="middle" dominant-baseline="middle" font-family="system-ui, -apple-system, BlinkMacSystemFont, Segoe UI, sans-serif" font-size="96" font-weight="700" fill="{code_text}">{status_code}</text>
+  <rect x="{name_box_x}" y="{name_box_y}" width="{name_box_width}" height="{name_box_height}" rx="24" fill="{panel}" stroke="{border}" stroke-width="2"/>
+  <text x="{text_center_x}" y="{name_y}" text-anchor="middle" dominant-baseline="middle" font-family="system-ui, -apple-system, BlinkMacSystemFont, Segoe UI, sans-serif" font-size="44" font-weight="600" fill="{name_text}">{status_name}</text>
 </svg>
 "##,
         width_minus_padding = width - (padding * 2),
@@ -372,27 +549,74 @@ async fn random_pet_with_status(
     Ok(candidates.choose(&mut rng).cloned())
 }
 
-pub(crate) async fn not_found_response(state: &AppState) -> Response {
+pub(crate) async fn not_found_response(
+    state: &AppState,
+    base_domain: &str,
+    reason: Option<String>,
+    no_pets: bool,
+) -> Response {
     let image_url = random_404_image_url(state).await;
     let has_image = image_url.is_some();
     let mut response = NotFoundTemplate {
         has_image,
         image_url: image_url.unwrap_or_default(),
-        frontend_url: frontend_url_for_state(state),
+        frontend_url: state.base_url_for(base_domain),
+        reason,
+        no_pets,
+        theme_stylesheet: state.theme_stylesheet(),
     }
     .into_response();
     *response.status_mut() = StatusCode::NOT_FOUND;
     response
 }
 
-pub(crate) async fn about_view(State(state): State<AppState>) -> Result<Response, HttpetError> {
+pub(crate) async fn about_view(
+    domain: AnimalDomain,
+    State(state): State<AppState>,
+) -> Result<Response, HttpetError> {
     Ok(AboutTemplate {
-        frontend_url: frontend_url_for_state(&state),
-        pet_example_url: state.pet_base_url("dog"),
+        frontend_url: state.base_url_for(&domain.base_domain),
+        pet_example_url: state.pet_base_url(&domain.base_domain, "dog"),
+        subdomains: !state.config.disable_subdomains,
+        theme_stylesheet: state.theme_stylesheet(),
     }
     .into_response())
 }
 
+/// Lists the home, about and every enabled pet's pages for search engines,
+/// using the canonical public URL.
+pub(crate) async fn sitemap_handler(
+    State(state): State<AppState>,
+) -> Result<Response, HttpetError> {
+    let base_url = state.public_base_url();
+    let mut urls = vec![format!("{base_url}/"), format!("{base_url}/about")];
+    let enabled = state.enabled_pets.read().await.clone();
+    for pet in enabled {
+        urls.push(format!("{base_url}/{pet}"));
+        for status_code in status_codes_for(&state.image_dir, &pet).await? {
+            urls.push(format!("{base_url}/info/{pet}/{status_code}"));
+        }
+    }
+
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for url in urls {
+        sitemap.push_str(&format!(
+            "  <url><loc>{}</loc></url>\n",
+            html_escape::encode_text(&url)
+        ));
+    }
+    sitemap.push_str("</urlset>\n");
+
+    let mut response = Response::new(axum::body::Body::from(sitemap));
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/xml; charset=utf-8"),
+    );
+    Ok(response)
+}
+
 /// handles the / GET
 pub(crate) async fn root_handler(
     domain: AnimalDomain,
@@ -402,7 +626,7 @@ pub(crate) async fn root_handler(
     // if it's a subdomain then handle that.
     if let Some(animal) = domain.animal.as_deref() {
         let animal = normalize_pet_name_strict(animal)?;
-        return pet_status_list_subdomain(state, &animal).await;
+        return pet_status_list_subdomain(state, &domain.base_domain, &animal).await;
     }
 
     let db = &state.db;
@@ -413,6 +637,7 @@ pub(crate) async fn root_handler(
     let top_query = Query::select()
         .from(pets::Entity)
         .column(pets::Column::Name)
+        .column(pets::Column::Votable)
         .expr_as(
             Expr::col(votes::Column::VoteCount).sum(),
             Alias::new("total_votes"),
@@ -428,6 +653,11 @@ pub(crate) async fn root_handler(
         .and_where(Expr::col((votes::Entity, votes::Column::VoteDate)).lte(today))
         .group_by_col((pets::Entity, pets::Column::Id))
         .group_by_col((pets::Entity, pets::Column::Name))
+        .group_by_col((pets::Entity, pets::Column::Votable))
+        .and_having(
+            Expr::expr(Expr::col(votes::Column::VoteCount).sum())
+                .gte(i64::from(state.config.leaderboard_min_votes)),
+        )
         .order_by(Alias::new("total_votes"), Order::Desc)
         .limit(10)
         .to_owned();
@@ -438,33 +668,40 @@ pub(crate) async fn root_handler(
     for row in rows {
         let name: String = row.try_get("", "name")?;
         let votes: i64 = row.try_get("", "total_votes")?;
-        top_pets.push(TopPet { name, votes });
+        let votable: bool = row.try_get("", "votable")?;
+        top_pets.push(TopPet {
+            name,
+            votes,
+            votable,
+        });
     }
+    let all_time_top_pets = state.all_time_top_pets().await?;
     let csrf_token = csrf::csrf_token(&session).await?;
     Ok(HomeTemplate {
         enabled_pets,
         top_pets,
+        all_time_top_pets,
         state: state.clone(),
+        frontend_url: state.base_url_for(&domain.base_domain),
+        base_domain: domain.base_domain,
         csrf_token,
-        frontend_url: frontend_url_for_state(&state),
+        theme_stylesheet: state.theme_stylesheet(),
     }
     .into_response())
 }
 
-pub(crate) fn frontend_url_for_state(state: &AppState) -> String {
-    if let Some(url) = state.frontend_url.as_ref() {
-        url.to_string().trim_end_matches('/').to_string()
-    } else if state.listen_port == 443 {
-        format!("https://{}", state.base_domain)
-    } else if state.listen_port == 80 {
-        format!("http://{}", state.base_domain)
-    } else {
-        format!("http://{}:{}", state.base_domain, state.listen_port)
-    }
+async fn random_404_image_url(state: &AppState) -> Option<String> {
+    let pool = state.not_found_pool().await;
+    let mut rng = rand::rng();
+    let pet = pool.choose(&mut rng)?;
+    Some(format!("/{pet}/404"))
 }
 
-async fn random_404_image_url(state: &AppState) -> Option<String> {
-    let mut entries = fs::read_dir(&state.image_dir).await.ok()?;
+/// Scans the image directory for pets that have a 404 image.
+pub(crate) async fn scan_not_found_pool(image_dir: &std::path::Path) -> Vec<String> {
+    let Ok(mut entries) = fs::read_dir(image_dir).await else {
+        return Vec::new();
+    };
     let mut candidates = Vec::new();
     while let Ok(Some(entry)) = entries.next_entry().await {
         let file_type = match entry.file_type().await {
@@ -486,10 +723,5 @@ async fn random_404_image_url(state: &AppState) -> Option<String> {
             candidates.push(dir_name);
         }
     }
-    if candidates.is_empty() {
-        return None;
-    }
-    let mut rng = rand::rng();
-    let pet = candidates.choose(&mut rng)?;
-    Some(format!("/{pet}/404"))
+    candidates
 }